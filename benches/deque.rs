@@ -0,0 +1,65 @@
+use algo_ds_rs::basic_data_structures::deque::Deque;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+// The previous Vec backed deque, kept here as the baseline to compare against
+struct VecBackedDeque<T> {
+    cap: usize,
+    data: Vec<T>,
+}
+
+impl<T> VecBackedDeque<T> {
+    fn new(size: usize) -> Self {
+        Self {
+            cap: size,
+            data: Vec::with_capacity(size),
+        }
+    }
+
+    fn add_rear(&mut self, val: T) -> Result<(), &str> {
+        if self.data.len() == self.cap {
+            return Err("No space available");
+        }
+        self.data.insert(0, val);
+        Ok(())
+    }
+
+    fn remove_rear(&mut self) -> Option<T> {
+        if !self.data.is_empty() {
+            Some(self.data.remove(0))
+        } else {
+            None
+        }
+    }
+}
+
+fn bench_rear_operations(c: &mut Criterion) {
+    let mut group = c.benchmark_group("deque_rear");
+    for size in [100, 1_000, 10_000] {
+        group.bench_with_input(BenchmarkId::new("ring_buffer", size), &size, |b, &size| {
+            b.iter(|| {
+                let mut deque = Deque::new(size);
+                for i in 0..size {
+                    deque.add_rear(i).unwrap();
+                }
+                while let Some(val) = deque.remove_rear() {
+                    black_box(val);
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("vec_insert", size), &size, |b, &size| {
+            b.iter(|| {
+                let mut deque = VecBackedDeque::new(size);
+                for i in 0..size {
+                    deque.add_rear(i).unwrap();
+                }
+                while let Some(val) = deque.remove_rear() {
+                    black_box(val);
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_rear_operations);
+criterion_main!(benches);
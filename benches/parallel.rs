@@ -0,0 +1,40 @@
+use algo_ds_rs::sort::merge::merge_sort;
+use algo_ds_rs::sort::parallel::{par_merge_sort, par_quick_sort};
+use algo_ds_rs::sort::quick::quick_sort;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+
+type Sort = fn(&mut [u64]);
+
+// Serial against parallel on the same random input, the ratio is the speedup
+fn random(len: usize) -> Vec<u64> {
+    let mut seed: u64 = 42;
+    (0..len)
+        .map(|_| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+            seed >> 16
+        })
+        .collect()
+}
+
+fn bench_parallel_sorts(c: &mut Criterion) {
+    let sorts: [(&str, Sort); 4] = [
+        ("merge_sort", merge_sort),
+        ("par_merge_sort", par_merge_sort),
+        ("quick_sort", quick_sort),
+        ("par_quick_sort", par_quick_sort),
+    ];
+    for len in [100_000, 1_000_000] {
+        let mut group = c.benchmark_group(format!("parallel_sort_{}", len));
+        group.sample_size(20);
+        let input = random(len);
+        for (name, sort) in sorts {
+            group.bench_with_input(BenchmarkId::new(name, "random"), &input, |b, input| {
+                b.iter_batched_ref(|| input.clone(), |v| sort(v), BatchSize::LargeInput)
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_parallel_sorts);
+criterion_main!(benches);
@@ -0,0 +1,56 @@
+use algo_ds_rs::sort::pdq::pdq_sort;
+use algo_ds_rs::sort::quick::quick_sort;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+
+// Inputs that quicksorts are usually tuned against
+fn inputs(len: usize) -> Vec<(&'static str, Vec<u64>)> {
+    let mut seed: u64 = 42;
+    let random: Vec<u64> = (0..len)
+        .map(|_| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+            seed >> 16
+        })
+        .collect();
+    let sorted: Vec<u64> = (0..len as u64).collect();
+    let reversed: Vec<u64> = sorted.iter().rev().copied().collect();
+    let few_unique: Vec<u64> = random.iter().map(|x| x % 8).collect();
+    let half = len as u64 / 2;
+    let organ_pipe: Vec<u64> = (0..half).chain((0..half).rev()).collect();
+
+    vec![
+        ("random", random),
+        ("sorted", sorted),
+        ("reversed", reversed),
+        ("few_unique", few_unique),
+        ("organ_pipe", organ_pipe),
+    ]
+}
+
+fn bench_unstable_sorts(c: &mut Criterion) {
+    for len in [1_000, 100_000] {
+        let mut group = c.benchmark_group(format!("unstable_sort_{}", len));
+        for (name, input) in inputs(len) {
+            group.bench_with_input(BenchmarkId::new("pdq_sort", name), &input, |b, input| {
+                b.iter_batched_ref(|| input.clone(), |v| pdq_sort(v), BatchSize::LargeInput)
+            });
+            group.bench_with_input(BenchmarkId::new("quick_sort", name), &input, |b, input| {
+                b.iter_batched_ref(|| input.clone(), |v| quick_sort(v), BatchSize::LargeInput)
+            });
+            group.bench_with_input(
+                BenchmarkId::new("std_sort_unstable", name),
+                &input,
+                |b, input| {
+                    b.iter_batched_ref(
+                        || input.clone(),
+                        |v| v.sort_unstable(),
+                        BatchSize::LargeInput,
+                    )
+                },
+            );
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_unstable_sorts);
+criterion_main!(benches);
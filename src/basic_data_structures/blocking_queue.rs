@@ -0,0 +1,161 @@
+use super::policy::OverflowPolicy;
+use super::queue::Queue;
+use crate::error::CapacityError;
+use std::sync::{Condvar, Mutex, MutexGuard};
+
+// A Queue shared between threads
+// With OverflowPolicy::Block producers wait for room instead of being rejected
+// and consumers can always wait for an element to arrive
+pub struct BlockingQueue<T> {
+    queue: Mutex<Queue<T>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+impl<T> BlockingQueue<T> {
+    //Constructor, producers block while the queue is full
+    pub fn new(size: usize) -> Self {
+        Self::with_policy(size, OverflowPolicy::Block)
+    }
+
+    //Constructor with a chosen behaviour for when the queue is full
+    pub fn with_policy(size: usize, policy: OverflowPolicy) -> Self {
+        Self {
+            queue: Mutex::new(Queue::with_policy(size, policy)),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Queue<T>> {
+        self.queue.lock().unwrap()
+    }
+
+    //Check empty
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    //Check length
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    //Insert element, waiting for room first if the policy is Block
+    //A zero capacity queue with the Block policy waits forever
+    pub fn enqueue(&self, val: T) -> Result<Option<T>, CapacityError<T>> {
+        let mut queue = self.lock();
+        if queue.policy() == OverflowPolicy::Block {
+            while queue.is_full() {
+                queue = self.not_full.wait(queue).unwrap();
+            }
+        }
+        let result = queue.enqueue(val);
+        if result.is_ok() {
+            self.not_empty.notify_one();
+        }
+        result
+    }
+
+    //Pop out values, waiting until one is available
+    pub fn dequeue(&self) -> T {
+        let mut queue = self.lock();
+        loop {
+            if let Some(val) = queue.dequeue() {
+                self.not_full.notify_one();
+                return val;
+            }
+            queue = self.not_empty.wait(queue).unwrap();
+        }
+    }
+
+    //Pop out values without waiting
+    pub fn try_dequeue(&self) -> Option<T> {
+        let val = self.lock().dequeue();
+        if val.is_some() {
+            self.not_full.notify_one();
+        }
+        val
+    }
+
+    //Take back the queue once no other thread uses it
+    pub fn into_inner(self) -> Queue<T> {
+        self.queue.into_inner().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_enqueue_dequeue_blocking_queue() {
+        let queue = BlockingQueue::new(2);
+        assert!(queue.is_empty());
+
+        queue.enqueue(1).expect("is not at full cap");
+        queue.enqueue(2).expect("is not at full cap");
+        assert_eq!(queue.len(), 2);
+
+        assert_eq!(queue.dequeue(), 1);
+        assert_eq!(queue.try_dequeue(), Some(2));
+        assert_eq!(queue.try_dequeue(), None);
+    }
+
+    #[test]
+    fn test_producer_waits_for_room_blocking_queue() {
+        let queue = BlockingQueue::new(1);
+        let enqueued = AtomicUsize::new(0);
+
+        thread::scope(|s| {
+            s.spawn(|| {
+                for i in 0..3 {
+                    queue.enqueue(i).expect("blocks instead of rejecting");
+                    enqueued.fetch_add(1, Ordering::SeqCst);
+                }
+            });
+
+            // the producer fills the single slot and then has to wait
+            while enqueued.load(Ordering::SeqCst) == 0 {
+                thread::yield_now();
+            }
+            thread::sleep(Duration::from_millis(20));
+            assert_eq!(enqueued.load(Ordering::SeqCst), 1);
+
+            assert_eq!(queue.dequeue(), 0);
+            assert_eq!(queue.dequeue(), 1);
+            assert_eq!(queue.dequeue(), 2);
+        });
+
+        assert_eq!(enqueued.load(Ordering::SeqCst), 3);
+        assert!(queue.into_inner().is_empty());
+    }
+
+    #[test]
+    fn test_consumer_waits_for_element_blocking_queue() {
+        let queue = BlockingQueue::new(4);
+
+        thread::scope(|s| {
+            let consumer = s.spawn(|| (0..100).map(|_| queue.dequeue()).sum::<i32>());
+            for i in 0..100 {
+                queue.enqueue(i).expect("blocks instead of rejecting");
+            }
+            assert_eq!(consumer.join().unwrap(), (0..100).sum());
+        });
+    }
+
+    #[test]
+    fn test_other_policies_blocking_queue() {
+        let queue = BlockingQueue::with_policy(1, OverflowPolicy::Reject);
+        queue.enqueue(1).expect("is not at full cap");
+        assert_eq!(queue.enqueue(2).unwrap_err().element(), 2);
+
+        let queue = BlockingQueue::with_policy(1, OverflowPolicy::OverwriteOldest);
+        queue.enqueue(1).expect("is not at full cap");
+        assert_eq!(queue.enqueue(2), Ok(Some(1)));
+        assert_eq!(queue.dequeue(), 2);
+    }
+}
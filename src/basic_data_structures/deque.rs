@@ -1,60 +1,161 @@
-#[derive(Debug)]
+use super::policy::OverflowPolicy;
+use super::ring;
+use crate::error::CapacityError;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::FusedIterator;
+use std::slice;
+
+#[derive(Clone)]
 pub struct Deque<T> {
     cap: usize,
-    data: Vec<T>,
+    // index of the rear element
+    head: usize,
+    // index one past the front element
+    tail: usize,
+    len: usize,
+    data: Vec<Option<T>>,
+    policy: OverflowPolicy,
 }
 
 impl<T> Deque<T> {
-    //Constructor
+    //Constructor, rejects new elements once full
     pub fn new(size: usize) -> Self {
+        Self::with_policy(size, OverflowPolicy::Reject)
+    }
+
+    //Constructor with a chosen behaviour for when the deque is full
+    pub fn with_policy(size: usize, policy: OverflowPolicy) -> Self {
+        let mut data = Vec::with_capacity(size);
+        data.resize_with(size, || None);
         Self {
             cap: size,
-            data: Vec::with_capacity(size),
+            head: 0,
+            tail: 0,
+            len: 0,
+            data,
+            policy,
+        }
+    }
+
+    //Constructor for a deque that grows as needed, never rejecting an element
+    pub fn unbounded() -> Self {
+        Self::with_policy(0, OverflowPolicy::Grow)
+    }
+
+    //Check overflow policy
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    //Check capacity, the bound of a bounded deque or the allocated size of an unbounded one
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    //Make room for at least additional more elements, growing at least double to stay amortised O(1)
+    //Only an unbounded deque reallocates, a bounded one already holds every slot of its bound
+    pub fn reserve(&mut self, additional: usize) {
+        let required = self.len + additional;
+        if self.policy == OverflowPolicy::Grow && required > self.cap {
+            self.reallocate(required.max(self.cap * 2));
+        }
+    }
+
+    //Drop unused slots of an unbounded deque
+    //A bounded one keeps its bound, set_capacity is the way to change it
+    pub fn shrink_to_fit(&mut self) {
+        if self.policy == OverflowPolicy::Grow && self.cap > self.len {
+            self.reallocate(self.len);
         }
     }
 
+    //Change the capacity, panics if it is smaller than the current len
+    pub fn set_capacity(&mut self, cap: usize) {
+        assert!(
+            cap >= self.len,
+            "capacity {} is smaller than the len {}",
+            cap,
+            self.len
+        );
+        self.reallocate(cap);
+    }
+
     //Check empty
     pub fn is_empty(&self) -> bool {
         0 == self.len()
     }
 
-    //Check full
+    //Check full, an unbounded deque never is
     pub fn is_full(&self) -> bool {
-        self.len() == self.cap
+        self.policy != OverflowPolicy::Grow && self.len() == self.cap
     }
 
     //Check length
     pub fn len(&self) -> usize {
-        self.data.len()
+        self.len
     }
 
     //Clear deque
     pub fn clear(&mut self) {
-        self.data = Vec::with_capacity(self.cap);
+        for slot in self.data.iter_mut() {
+            *slot = None;
+        }
+        self.head = 0;
+        self.tail = 0;
+        self.len = 0;
     }
 
     //Push to the front
-    pub fn add_front(&mut self, val: T) -> Result<(), &str> {
+    //When full the overflow policy applies, OverwriteOldest evicts the opposite end: the rear
+    pub fn add_front(&mut self, val: T) -> Result<Option<T>, CapacityError<T>> {
+        let mut evicted = None;
         if self.len() == self.cap {
-            return Err("No space available");
+            match self.policy {
+                OverflowPolicy::Reject | OverflowPolicy::Block => {
+                    return Err(CapacityError::new(val));
+                }
+                OverflowPolicy::OverwriteOldest if self.cap == 0 => {
+                    return Err(CapacityError::new(val));
+                }
+                OverflowPolicy::OverwriteOldest => evicted = self.remove_rear(),
+                OverflowPolicy::Grow => self.reallocate((self.cap * 2).max(1)),
+            }
         }
-        self.data.push(val);
-        Ok(())
+        self.data[self.tail] = Some(val);
+        self.tail = self.wrap_add(self.tail, 1);
+        self.len += 1;
+        Ok(evicted)
     }
 
     //Push to the rear
-    pub fn add_rear(&mut self, val: T) -> Result<(), &str> {
+    //When full the overflow policy applies, OverwriteOldest evicts the opposite end: the front
+    pub fn add_rear(&mut self, val: T) -> Result<Option<T>, CapacityError<T>> {
+        let mut evicted = None;
         if self.len() == self.cap {
-            return Err("No space available");
+            match self.policy {
+                OverflowPolicy::Reject | OverflowPolicy::Block => {
+                    return Err(CapacityError::new(val));
+                }
+                OverflowPolicy::OverwriteOldest if self.cap == 0 => {
+                    return Err(CapacityError::new(val));
+                }
+                OverflowPolicy::OverwriteOldest => evicted = self.remove_front(),
+                OverflowPolicy::Grow => self.reallocate((self.cap * 2).max(1)),
+            }
         }
-        self.data.insert(0, val);
-        Ok(())
+        self.head = self.wrap_sub(self.head, 1);
+        self.data[self.head] = Some(val);
+        self.len += 1;
+        Ok(evicted)
     }
 
     //Pop from the front
     pub fn remove_front(&mut self) -> Option<T> {
-        if self.len() > 0 {
-            self.data.pop()
+        if !self.is_empty() {
+            self.tail = self.wrap_sub(self.tail, 1);
+            self.len -= 1;
+            self.data[self.tail].take()
         } else {
             None
         }
@@ -62,8 +163,11 @@ impl<T> Deque<T> {
 
     //Pop from the rear
     pub fn remove_rear(&mut self) -> Option<T> {
-        if self.len() > 0 {
-            Some(self.data.remove(0))
+        if !self.is_empty() {
+            let val = self.data[self.head].take();
+            self.head = self.wrap_add(self.head, 1);
+            self.len -= 1;
+            val
         } else {
             None
         }
@@ -71,244 +175,422 @@ impl<T> Deque<T> {
 
     //Peek Queue
     pub fn peek_front(&self) -> Option<&T> {
-        self.data.last()
+        if !self.is_empty() {
+            self.data[self.wrap_sub(self.tail, 1)].as_ref()
+        } else {
+            None
+        }
     }
     pub fn peek_rear(&self) -> Option<&T> {
-        self.data.first()
+        if !self.is_empty() {
+            self.data[self.head].as_ref()
+        } else {
+            None
+        }
+    }
+
+    // Ring buffer index arithmetic, only called while cap > 0
+    fn wrap_add(&self, index: usize, offset: usize) -> usize {
+        (index + offset) % self.cap
+    }
+
+    fn wrap_sub(&self, index: usize, offset: usize) -> usize {
+        (index + self.cap - offset) % self.cap
+    }
+
+    // Move the elements into a buffer of new_cap slots, unwrapped to start at slot 0
+    fn reallocate(&mut self, new_cap: usize) {
+        self.data = ring::reallocate(&mut self.data, self.head, self.len, new_cap);
+        self.cap = new_cap;
+        self.head = 0;
+        self.tail = if self.len == new_cap { 0 } else { self.len };
+    }
+
+    fn as_slices(&self) -> (&[Option<T>], &[Option<T>]) {
+        ring::as_slices(&self.data, self.head, self.len)
+    }
+
+    fn as_mut_slices(&mut self) -> (&mut [Option<T>], &mut [Option<T>]) {
+        ring::as_mut_slices(&mut self.data, self.head, self.len)
     }
 
     // Implementation of iterations for a deque
     // into_iter(): deque modified and became a iterator
     // iter(): deque unmodified and get a immutable iterator
     // iter_mut(): deque unmodified and get a mutable iterator
-    pub fn into_iter(self) -> IntoIter<T> {
-        IntoIter(self)
-    }
-
-    pub fn iter(&self) -> Iter<T> {
-        let mut iterator = Iter { stack: Vec::new() };
-        for item in self.data.iter() {
-            iterator.stack.push(item);
+    pub fn iter(&self) -> Iter<'_, T> {
+        let (first, second) = self.as_slices();
+        Iter {
+            first: first.iter(),
+            second: second.iter(),
         }
-        iterator
     }
 
-    pub fn iter_mut(&mut self) -> IterMut<T> {
-        let mut iterator = IterMut { stack: Vec::new() };
-        for item in self.data.iter_mut() {
-            iterator.stack.push(item);
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        let (first, second) = self.as_mut_slices();
+        IterMut {
+            first: first.iter_mut(),
+            second: second.iter_mut(),
         }
-        iterator
     }
 }
 
 // Implementation of 3 iterations
+// All of them run from the rear to the front and can be reversed
 pub struct IntoIter<T>(Deque<T>);
-impl<T: Clone> Iterator for IntoIter<T> {
+impl<T> Iterator for IntoIter<T> {
     type Item = T;
     fn next(&mut self) -> Option<Self::Item> {
-        if !self.0.is_empty() {
-            Some(self.0.data.remove(0))
-        } else {
-            None
-        }
+        self.0.remove_rear()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len(), Some(self.0.len()))
     }
 }
 
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.remove_front()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> FusedIterator for IntoIter<T> {}
+
 pub struct Iter<'a, T: 'a> {
-    stack: Vec<&'a T>,
+    first: slice::Iter<'a, Option<T>>,
+    second: slice::Iter<'a, Option<T>>,
 }
 impl<'a, T> Iterator for Iter<'a, T> {
     type Item = &'a T;
     fn next(&mut self) -> Option<Self::Item> {
-        if 0 != self.stack.len() {
-            Some(self.stack.remove(0))
-        } else {
-            None
-        }
+        self.first
+            .next()
+            .or_else(|| self.second.next())
+            .and_then(Option::as_ref)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.first.len() + self.second.len();
+        (len, Some(len))
     }
 }
 
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.second
+            .next_back()
+            .or_else(|| self.first.next_back())
+            .and_then(Option::as_ref)
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
 pub struct IterMut<'a, T: 'a> {
-    stack: Vec<&'a mut T>,
+    first: slice::IterMut<'a, Option<T>>,
+    second: slice::IterMut<'a, Option<T>>,
 }
 impl<'a, T> Iterator for IterMut<'a, T> {
     type Item = &'a mut T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if 0 != self.stack.len() {
-            Some(self.stack.remove(0))
-        } else {
-            None
+        self.first
+            .next()
+            .or_else(|| self.second.next())
+            .and_then(Option::as_mut)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.first.len() + self.second.len();
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.second
+            .next_back()
+            .or_else(|| self.first.next_back())
+            .and_then(Option::as_mut)
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+// Implementation of the std collection traits
+// Collecting and extending add to the front, so a deque collects back in iteration order
+// Default is unbounded, and extending goes through the overflow policy like add_front does:
+// a full deque grows or drops what it evicts, and panics rather than go past a Reject or Block bound
+impl<T> Default for Deque<T> {
+    fn default() -> Self {
+        Self::unbounded()
+    }
+}
+
+impl<T> FromIterator<T> for Deque<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut deque = Self::default();
+        deque.extend(iter);
+        deque
+    }
+}
+
+impl<T> Extend<T> for Deque<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        if self.policy == OverflowPolicy::Grow {
+            self.reserve(iter.size_hint().0);
+        }
+        for val in iter {
+            if self.add_front(val).is_err() {
+                panic!("extend overflowed a full deque of capacity {}", self.cap);
+            }
         }
     }
 }
 
+impl<T> IntoIterator for Deque<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Deque<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut Deque<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+// Equality and hashing only look at the elements, not at the capacity
+impl<T: PartialEq> PartialEq for Deque<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for Deque<T> {}
+
+impl<T: Hash> Hash for Deque<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        for val in self.iter() {
+            val.hash(state);
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Deque<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::basic_data_structures::test_utils::hash_of;
+
+    // Builds a deque whose elements read rear to front as `items`
+    fn deque_from(cap: usize, items: &[i32]) -> Deque<i32> {
+        let mut deque = Deque::new(cap);
+        for &item in items {
+            deque.add_front(item).expect("is not at full cap");
+        }
+        deque
+    }
 
     #[test]
     fn test_create_deque() {
         let deque: Deque<i32> = Deque::new(5);
         assert_eq!(deque.cap, 5);
-        assert_eq!(deque.data.len(), 0);
+        assert_eq!(deque.len, 0);
+        assert_eq!(deque.data.len(), 5);
     }
 
     #[test]
     fn test_check_empty_deque() {
-        let deque: Deque<i32> = Deque {
-            cap: 5,
-            data: vec![],
-        };
+        let deque: Deque<i32> = Deque::new(5);
         assert!(deque.is_empty());
     }
 
     #[test]
     fn test_check_full_deque() {
-        let deque_a: Deque<i32> = Deque {
-            cap: 5,
-            data: vec![],
-        };
+        let deque_a = deque_from(5, &[]);
         assert!(!deque_a.is_full());
 
-        let deque_b: Deque<i32> = Deque {
-            cap: 5,
-            data: vec![1, 1, 1, 1, 1],
-        };
+        let deque_b = deque_from(5, &[1, 1, 1, 1, 1]);
         assert!(deque_b.is_full());
+
+        let deque_c: Deque<i32> = Deque::new(0);
+        assert!(deque_c.is_full());
     }
 
     #[test]
     fn test_clear_deque() {
-        let mut deque: Deque<i32> = Deque {
-            cap: 3,
-            data: vec![1, 2, 3],
-        };
+        let mut deque = deque_from(3, &[1, 2, 3]);
         deque.clear();
         assert!(deque.is_empty());
+        assert!(deque.data.iter().all(Option::is_none));
     }
 
     #[test]
     fn test_add_front_deque() {
-        let mut deque_a: Deque<i32> = Deque {
-            cap: 5,
-            data: vec![1, 2, 3],
-        };
+        let mut deque_a = deque_from(5, &[1, 2, 3]);
         deque_a.add_front(4).expect("is not at full cap");
-        assert_eq!(deque_a.data, vec![1, 2, 3, 4]);
+        assert_eq!(deque_a.iter().collect::<Vec<_>>(), vec![&1, &2, &3, &4]);
 
-        let mut deque_b: Deque<i32> = Deque {
-            cap: 3,
-            data: vec![1, 2, 3],
-        };
+        let mut deque_b = deque_from(3, &[1, 2, 3]);
         match deque_b.add_front(4) {
             Ok(..) => (),
             Err(e) => eprintln!("{}", e),
         }
-        assert_eq!(deque_b.data.len(), 3);
+        assert_eq!(deque_b.len(), 3);
     }
 
     #[test]
     fn test_add_rear_deque() {
-        let mut deque_a: Deque<i32> = Deque {
-            cap: 5,
-            data: vec![1, 2, 3],
-        };
+        let mut deque_a = deque_from(5, &[1, 2, 3]);
         deque_a.add_rear(4).expect("is not at full cap");
-        assert_eq!(deque_a.data, vec![4, 1, 2, 3]);
+        assert_eq!(deque_a.iter().collect::<Vec<_>>(), vec![&4, &1, &2, &3]);
 
-        let mut deque_b: Deque<i32> = Deque {
-            cap: 3,
-            data: vec![1, 2, 3],
-        };
+        let mut deque_b = deque_from(3, &[1, 2, 3]);
         match deque_b.add_rear(4) {
             Ok(..) => (),
             Err(e) => eprintln!("{}", e),
         }
-        assert_eq!(deque_b.data.len(), 3);
+        assert_eq!(deque_b.len(), 3);
     }
 
     #[test]
     fn test_remove_front_deque() {
-        let mut deque_a: Deque<i32> = Deque {
-            cap: 5,
-            data: vec![1, 2, 3],
-        };
-        deque_a.remove_front();
-        assert_eq!(deque_a.data, vec![1, 2]);
+        let mut deque_a = deque_from(5, &[1, 2, 3]);
+        assert_eq!(deque_a.remove_front(), Some(3));
+        assert_eq!(deque_a.iter().collect::<Vec<_>>(), vec![&1, &2]);
 
-        let mut deque_b: Deque<i32> = Deque {
-            cap: 5,
-            data: vec![],
-        };
-        deque_b.remove_front();
-        assert_eq!(deque_b.data, vec![]);
+        let mut deque_b = deque_from(5, &[]);
+        assert_eq!(deque_b.remove_front(), None);
+        assert!(deque_b.is_empty());
     }
 
     #[test]
     fn test_remove_rear_deque() {
-        let mut deque_a: Deque<i32> = Deque {
-            cap: 5,
-            data: vec![1, 2, 3],
-        };
-        deque_a.remove_rear();
-        assert_eq!(deque_a.data, vec![2, 3]);
+        let mut deque_a = deque_from(5, &[1, 2, 3]);
+        assert_eq!(deque_a.remove_rear(), Some(1));
+        assert_eq!(deque_a.iter().collect::<Vec<_>>(), vec![&2, &3]);
 
-        let mut deque_b: Deque<i32> = Deque {
-            cap: 5,
-            data: vec![],
-        };
-        deque_b.remove_rear();
-        assert_eq!(deque_b.data, vec![]);
+        let mut deque_b = deque_from(5, &[]);
+        assert_eq!(deque_b.remove_rear(), None);
+        assert!(deque_b.is_empty());
     }
 
     #[test]
     fn test_peek_front_deque() {
-        let deque_a: Deque<i32> = Deque {
-            cap: 5,
-            data: vec![1, 2, 3],
-        };
+        let deque_a = deque_from(5, &[1, 2, 3]);
         assert_eq!(deque_a.peek_front(), Some(&3));
 
-        let deque_b: Deque<i32> = Deque {
-            cap: 2,
-            data: vec![],
-        };
+        let deque_b = deque_from(2, &[]);
         assert_eq!(deque_b.peek_front(), None);
     }
 
     #[test]
     fn test_peek_rear_deque() {
-        let deque_a: Deque<i32> = Deque {
-            cap: 5,
-            data: vec![1, 2, 3],
-        };
+        let deque_a = deque_from(5, &[1, 2, 3]);
         assert_eq!(deque_a.peek_rear(), Some(&1));
 
-        let deque_b: Deque<i32> = Deque {
-            cap: 2,
-            data: vec![],
-        };
+        let deque_b = deque_from(2, &[]);
         assert_eq!(deque_b.peek_rear(), None);
     }
 
     #[test]
     fn test_get_len_deque() {
-        let deque: Deque<i32> = Deque {
-            cap: 10,
-            data: vec![1, 2, 3],
-        };
+        let deque = deque_from(10, &[1, 2, 3]);
 
         assert_eq!(deque.len(), 3)
     }
 
+    #[test]
+    fn test_wraparound_front_deque() {
+        let mut deque = deque_from(4, &[1, 2, 3]);
+        assert_eq!(deque.remove_rear(), Some(1));
+        assert_eq!(deque.remove_rear(), Some(2));
+
+        // tail walks off the end of the buffer and back to slot 0
+        deque.add_front(4).expect("is not at full cap");
+        deque.add_front(5).expect("is not at full cap");
+        deque.add_front(6).expect("is not at full cap");
+        assert!(deque.is_full());
+        assert_eq!(deque.tail, 2);
+        assert_eq!(deque.iter().collect::<Vec<_>>(), vec![&3, &4, &5, &6]);
+
+        assert_eq!(deque.peek_front(), Some(&6));
+        assert_eq!(deque.peek_rear(), Some(&3));
+        assert_eq!(deque.remove_front(), Some(6));
+        assert_eq!(deque.remove_front(), Some(5));
+        assert_eq!(deque.remove_front(), Some(4));
+        assert_eq!(deque.remove_front(), Some(3));
+        assert_eq!(deque.remove_front(), None);
+    }
+
+    #[test]
+    fn test_wraparound_rear_deque() {
+        let mut deque: Deque<i32> = Deque::new(3);
+
+        // head walks backwards from slot 0 to the end of the buffer
+        deque.add_rear(1).expect("is not at full cap");
+        assert_eq!(deque.head, 2);
+        deque.add_rear(2).expect("is not at full cap");
+        deque.add_front(3).expect("is not at full cap");
+        assert!(deque.add_rear(4).is_err());
+        assert_eq!(deque.iter().collect::<Vec<_>>(), vec![&2, &1, &3]);
+
+        assert_eq!(deque.remove_rear(), Some(2));
+        assert_eq!(deque.remove_rear(), Some(1));
+        assert_eq!(deque.remove_rear(), Some(3));
+        assert!(deque.is_empty());
+        assert_eq!(deque.head, deque.tail);
+    }
+
+    #[test]
+    fn test_wraparound_many_cycles_deque() {
+        let mut deque: Deque<i32> = Deque::new(5);
+        for i in 0..100 {
+            deque.add_front(i).expect("is not at full cap");
+            deque.add_rear(-i).expect("is not at full cap");
+            if deque.len() > 3 {
+                assert_eq!(deque.remove_front(), Some(i));
+                assert_eq!(deque.remove_rear(), Some(-i));
+            }
+        }
+        assert_eq!(deque.len(), 2);
+        assert_eq!(deque.peek_rear(), Some(&0));
+        assert_eq!(deque.peek_front(), Some(&0));
+    }
+
     #[test]
     fn test_iter_deque() {
-        let mut deque: Deque<i32> = Deque {
-            cap: 10,
-            data: vec![1, 2, 3],
-        };
+        let mut deque = deque_from(10, &[1, 2, 3]);
 
         let mut iter = deque.iter();
 
@@ -323,10 +605,7 @@ mod tests {
 
     #[test]
     fn test_iter_mut_deque() {
-        let mut deque: Deque<i32> = Deque {
-            cap: 10,
-            data: vec![1, 2, 3],
-        };
+        let mut deque = deque_from(10, &[1, 2, 3]);
 
         let mut iter = deque.iter_mut();
 
@@ -341,10 +620,7 @@ mod tests {
 
     #[test]
     fn test_iter_mut_deque_2() {
-        let mut deque: Deque<i32> = Deque {
-            cap: 10,
-            data: vec![1, 2, 3],
-        };
+        let mut deque = deque_from(10, &[1, 2, 3]);
 
         let iter = deque.iter_mut();
 
@@ -367,10 +643,7 @@ mod tests {
 
     #[test]
     fn test_into_iter_deque() {
-        let deque: Deque<i32> = Deque {
-            cap: 10,
-            data: vec![1, 2, 3],
-        };
+        let deque = deque_from(10, &[1, 2, 3]);
 
         let mut iter = deque.into_iter();
 
@@ -382,4 +655,291 @@ mod tests {
         //Panics because into_iter consumes the queue
         //deque.add_front(1);
     }
+
+    #[test]
+    fn test_iter_double_ended_deque() {
+        let mut deque = deque_from(4, &[2, 3]);
+        deque.add_rear(1).expect("is not at full cap");
+        deque.add_front(4).expect("is not at full cap");
+        // occupied slots now straddle the end of the buffer
+        assert_eq!(deque.iter().collect::<Vec<_>>(), vec![&1, &2, &3, &4]);
+        assert_eq!(deque.iter().rev().collect::<Vec<_>>(), vec![&4, &3, &2, &1]);
+
+        let mut iter = deque.iter_mut();
+        assert_eq!(iter.len(), 4);
+        assert_eq!(iter.next_back(), Some(&mut 4));
+        assert_eq!(iter.next(), Some(&mut 1));
+        assert_eq!(iter.next_back(), Some(&mut 3));
+        assert_eq!(iter.len(), 1);
+        assert_eq!(iter.next(), Some(&mut 2));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+
+        let mut into_iter = deque.into_iter();
+        assert_eq!(into_iter.len(), 4);
+        assert_eq!(into_iter.next_back(), Some(4));
+        assert_eq!(into_iter.collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_into_iter_without_clone_deque() {
+        #[derive(Debug, PartialEq)]
+        struct NoClone(i32);
+
+        let mut deque = Deque::new(2);
+        deque.add_front(NoClone(1)).expect("is not at full cap");
+        deque.add_rear(NoClone(0)).expect("is not at full cap");
+
+        assert_eq!(
+            deque.into_iter().collect::<Vec<_>>(),
+            vec![NoClone(0), NoClone(1)]
+        );
+    }
+
+    #[test]
+    fn test_default_and_collect_deque() {
+        let deque: Deque<i32> = Deque::default();
+        assert!(deque.is_empty());
+        assert!(!deque.is_full());
+        assert_eq!(deque.policy(), OverflowPolicy::Grow);
+
+        let deque: Deque<i32> = (1..=3).collect();
+        assert_eq!(deque.len(), 3);
+        assert_eq!(deque.peek_rear(), Some(&1));
+        assert_eq!(deque.peek_front(), Some(&3));
+        assert_eq!(deque.iter().cloned().collect::<Deque<_>>(), deque);
+    }
+
+    #[test]
+    fn test_extend_grows_deque() {
+        let mut deque = Deque::with_policy(3, OverflowPolicy::Grow);
+        deque.add_front(1).expect("is not at full cap");
+        deque.add_rear(0).expect("is not at full cap");
+
+        // filter hides the length, so the buffer has to double while extending
+        deque.extend((2..=5).filter(|_| true));
+        assert_eq!(deque.len(), 6);
+        assert!(deque.cap >= 6);
+        assert_eq!(
+            deque.iter().collect::<Vec<_>>(),
+            vec![&0, &1, &2, &3, &4, &5]
+        );
+        assert_eq!(deque.remove_rear(), Some(0));
+        assert_eq!(deque.remove_front(), Some(5));
+    }
+
+    #[test]
+    fn test_extend_keeps_bound_deque() {
+        let mut deque = deque_from(4, &[1, 2]);
+        deque.extend(3..=4);
+        assert!(deque.is_full());
+        assert_eq!(deque.capacity(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "extend overflowed a full deque of capacity 3")]
+    fn test_extend_past_bound_deque() {
+        let mut deque = deque_from(3, &[1, 2]);
+        deque.extend(3..=4);
+    }
+
+    #[test]
+    fn test_for_loops_deque() {
+        let mut deque = deque_from(3, &[1, 2, 3]);
+
+        for x in &mut deque {
+            *x += 10;
+        }
+
+        let mut seen = Vec::new();
+        for x in &deque {
+            seen.push(*x);
+        }
+        assert_eq!(seen, vec![11, 12, 13]);
+
+        let mut owned = Vec::new();
+        for x in deque {
+            owned.push(x);
+        }
+        assert_eq!(owned, vec![11, 12, 13]);
+    }
+
+    #[test]
+    fn test_clone_eq_hash_deque() {
+        // same elements, but different capacities and buffer layouts
+        let deque_a = deque_from(3, &[1, 2, 3]);
+        let mut deque_b: Deque<i32> = Deque::new(5);
+        deque_b.add_front(2).expect("is not at full cap");
+        deque_b.add_rear(1).expect("is not at full cap");
+        deque_b.add_front(3).expect("is not at full cap");
+
+        assert_eq!(deque_a, deque_b);
+        assert_eq!(hash_of(&deque_a), hash_of(&deque_b));
+
+        let mut deque_c = deque_b.clone();
+        assert_eq!(deque_b, deque_c);
+        deque_c.remove_front();
+        assert_ne!(deque_b, deque_c);
+        assert_ne!(hash_of(&deque_b), hash_of(&deque_c));
+    }
+
+    #[test]
+    fn test_debug_deque() {
+        let deque = deque_from(5, &[1, 2, 3]);
+        assert_eq!(format!("{:?}", deque), "[1, 2, 3]");
+        assert_eq!(format!("{:?}", Deque::<i32>::new(2)), "[]");
+    }
+
+    #[test]
+    fn test_add_full_returns_element() {
+        let mut deque: Deque<String> = Deque::new(1);
+        deque
+            .add_front(String::from("kept"))
+            .expect("is not at full cap");
+
+        let err = deque.add_front(String::from("front")).unwrap_err();
+        assert_eq!(err.element(), "front");
+        let err = deque.add_rear(String::from("rear")).unwrap_err();
+        assert_eq!(err.element(), "rear");
+        assert_eq!(deque.len(), 1);
+    }
+
+    #[test]
+    fn test_overwrite_oldest_evicts_opposite_end_deque() {
+        let mut deque = Deque::with_policy(3, OverflowPolicy::OverwriteOldest);
+        assert_eq!(deque.policy(), OverflowPolicy::OverwriteOldest);
+
+        for i in 1..=3 {
+            assert_eq!(deque.add_front(i), Ok(None));
+        }
+        // adding at one end evicts from the other, even an element that was just added
+        assert_eq!(deque.add_front(4), Ok(Some(1)));
+        assert_eq!(deque.add_rear(0), Ok(Some(4)));
+        assert_eq!(deque.iter().collect::<Vec<_>>(), vec![&0, &2, &3]);
+
+        deque.extend(4..=5);
+        assert_eq!(deque.iter().collect::<Vec<_>>(), vec![&3, &4, &5]);
+
+        let mut empty = Deque::with_policy(0, OverflowPolicy::OverwriteOldest);
+        assert_eq!(empty.add_rear(1).unwrap_err().element(), 1);
+        assert_eq!(empty.add_front(2).unwrap_err().element(), 2);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_grow_policy_deque() {
+        let mut deque = Deque::with_policy(1, OverflowPolicy::Grow);
+        for i in 1..=3 {
+            assert_eq!(deque.add_front(i), Ok(None));
+            assert_eq!(deque.add_rear(-i), Ok(None));
+        }
+        // capacity doubles 1 -> 2 -> 4 -> 8
+        assert_eq!(deque.cap, 8);
+        assert_eq!(
+            deque.iter().collect::<Vec<_>>(),
+            vec![&-3, &-2, &-1, &1, &2, &3]
+        );
+    }
+
+    #[test]
+    fn test_reject_and_block_policies_deque() {
+        for policy in [OverflowPolicy::Reject, OverflowPolicy::Block] {
+            let mut deque = Deque::with_policy(1, policy);
+            assert_eq!(deque.add_front(1), Ok(None));
+            assert_eq!(deque.add_front(2).unwrap_err().element(), 2);
+            assert_eq!(deque.add_rear(3).unwrap_err().element(), 3);
+        }
+        assert_eq!(Deque::<i32>::new(1).policy(), OverflowPolicy::Reject);
+    }
+
+    #[test]
+    fn test_unbounded_deque() {
+        let mut deque = Deque::unbounded();
+        assert_eq!(deque.capacity(), 0);
+
+        for i in 0..500 {
+            assert_eq!(deque.add_front(i), Ok(None));
+            assert_eq!(deque.add_rear(-i), Ok(None));
+            assert!(!deque.is_full());
+        }
+        assert_eq!(deque.len(), 1000);
+        assert_eq!(deque.capacity(), 1024);
+        for i in (0..500).rev() {
+            assert_eq!(deque.remove_front(), Some(i));
+            assert_eq!(deque.remove_rear(), Some(-i));
+        }
+    }
+
+    #[test]
+    fn test_reserve_deque() {
+        let mut bounded = deque_from(3, &[1, 2]);
+        bounded.reserve(1);
+        assert_eq!(bounded.capacity(), 3);
+        // the bound only changes through set_capacity
+        bounded.reserve(4);
+        assert_eq!(bounded.capacity(), 3);
+        assert_eq!(bounded.iter().collect::<Vec<_>>(), vec![&1, &2]);
+
+        let mut unbounded: Deque<i32> = Deque::unbounded();
+        unbounded.extend(0..4);
+        unbounded.reserve(1);
+        assert_eq!(unbounded.capacity(), 8);
+    }
+
+    #[test]
+    fn test_shrink_to_fit_deque() {
+        // a bounded deque still takes elements up to its original capacity
+        let mut bounded = deque_from(10, &[1, 2, 3]);
+        bounded.shrink_to_fit();
+        assert_eq!(bounded.capacity(), 10);
+        for i in 4..=10 {
+            assert_eq!(bounded.add_front(i), Ok(None));
+        }
+        assert!(bounded.add_rear(0).is_err());
+
+        let mut ring = Deque::with_policy(4, OverflowPolicy::OverwriteOldest);
+        ring.add_front(1).expect("is not at full cap");
+        ring.shrink_to_fit();
+        for i in 2..=4 {
+            assert_eq!(ring.add_rear(i), Ok(None));
+        }
+
+        let mut unbounded: Deque<i32> = Deque::unbounded();
+        unbounded.extend(0..5);
+        unbounded.remove_front();
+        unbounded.shrink_to_fit();
+        assert_eq!(unbounded.capacity(), 4);
+        assert_eq!(unbounded.add_rear(-1), Ok(None));
+        assert_eq!(
+            unbounded.iter().collect::<Vec<_>>(),
+            vec![&-1, &0, &1, &2, &3]
+        );
+    }
+
+    #[test]
+    fn test_set_capacity_deque() {
+        let mut deque: Deque<i32> = Deque::new(3);
+        deque.add_front(2).expect("is not at full cap");
+        deque.add_rear(1).expect("is not at full cap");
+
+        // the ring is wrapped, raising the bound has to unwrap it
+        deque.set_capacity(4);
+        assert_eq!(deque.capacity(), 4);
+        deque.add_front(3).expect("is not at full cap");
+        deque.add_rear(0).expect("is not at full cap");
+        assert!(deque.is_full());
+        assert_eq!(deque.iter().collect::<Vec<_>>(), vec![&0, &1, &2, &3]);
+
+        deque.remove_rear();
+        deque.set_capacity(3);
+        assert!(deque.is_full());
+        assert_eq!(deque.iter().collect::<Vec<_>>(), vec![&1, &2, &3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity 1 is smaller than the len 2")]
+    fn test_set_capacity_below_len_deque() {
+        let mut deque = deque_from(2, &[1, 2]);
+        deque.set_capacity(1);
+    }
 }
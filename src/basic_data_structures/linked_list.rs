@@ -1,3 +1,13 @@
+use crate::error::IndexError;
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::thread;
+
+pub mod doubly;
+pub use doubly::{Cursor, CursorMut, DoublyLinkedList};
+
 type Link<T> = Option<Box<Node<T>>>;
 
 pub struct List<T> {
@@ -11,7 +21,7 @@ pub struct Node<T> {
     next: Link<T>,
 }
 
-impl<T: PartialEq> List<T> {
+impl<T> List<T> {
     //Constructor
     pub fn new() -> Self {
         Self {
@@ -41,19 +51,18 @@ impl<T: PartialEq> List<T> {
     }
 
     //Insert node at chosen index
-    pub fn insert_at(&mut self, index: usize, element: T) -> Result<(), &str> {
+    pub fn insert_at(&mut self, index: usize, element: T) -> Result<(), IndexError> {
+        if index > self.size {
+            return Err(IndexError {
+                index,
+                len: self.size,
+            });
+        }
         if index == 0 {
             self.push(element);
             return Ok(());
         }
-        let mut target = &mut self.head;
-        for _ in 0..index {
-            if let Some(node) = target {
-                target = &mut node.next;
-            } else {
-                return Err("Index out of bounds");
-            }
-        }
+        let target = self.link_at(index);
         let new_node = Box::new(Node {
             element,
             next: target.take(),
@@ -73,27 +82,142 @@ impl<T: PartialEq> List<T> {
     }
 
     //Delete node from chosen index
-    pub fn delete_at(&mut self, index: usize) -> Result<Option<T>, &str> {
-        if index == 0 {
-            return Ok(self.pop());
+    pub fn delete_at(&mut self, index: usize) -> Result<T, IndexError> {
+        if index >= self.size {
+            return Err(IndexError {
+                index,
+                len: self.size,
+            });
         }
+        let target = self.link_at(index);
+        let node = target.take().expect("index is within bounds");
+        *target = node.next;
+        self.size -= 1;
+        Ok(node.element)
+    }
+
+    //Link pointing at the node in the chosen index, the caller checks index <= size
+    fn link_at(&mut self, index: usize) -> &mut Link<T> {
         let mut target = &mut self.head;
         for _ in 0..index {
-            if let Some(node) = target {
-                target = &mut node.next;
+            target = &mut target.as_mut().expect("index is within bounds").next;
+        }
+        target
+    }
+
+    //Move every node of other to the tail, leaving other empty
+    pub fn append(&mut self, other: &mut Self) {
+        let tail = self.link_at(self.size);
+        *tail = other.head.take();
+        self.size += other.size;
+        other.size = 0;
+    }
+
+    //Split the list in two, the returned list starts at the chosen index
+    pub fn split_off(&mut self, index: usize) -> Result<Self, IndexError> {
+        if index > self.size {
+            return Err(IndexError {
+                index,
+                len: self.size,
+            });
+        }
+        let head = self.link_at(index).take();
+        let other = Self {
+            size: self.size - index,
+            head,
+        };
+        self.size = index;
+        Ok(other)
+    }
+
+    //Reverse the list in place by flipping every link
+    pub fn reverse(&mut self) {
+        let mut reversed: Link<T> = None;
+        let mut link = self.head.take();
+        while let Some(mut node) = link {
+            link = mem::replace(&mut node.next, reversed);
+            reversed = Some(node);
+        }
+        self.head = reversed;
+    }
+
+    //Keep only the elements matching the predicate, in their original order
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut keep: F) {
+        let mut link = self.head.take();
+        let mut tail = &mut self.head;
+        while let Some(mut node) = link {
+            link = node.next.take();
+            if keep(&node.element) {
+                tail = &mut tail.insert(node).next;
             } else {
-                return Err("Index out of bounds");
+                self.size -= 1;
             }
         }
-        if let Some(node) = target.take() {
-            *target = node.next;
-            self.size -= 1;
-            Ok(Some(node.element))
-        } else {
-            Ok(None)
+    }
+
+    //Remove consecutive elements for which same_bucket(current, previous) is true
+    pub fn dedup_by<F: FnMut(&mut T, &mut T) -> bool>(&mut self, mut same_bucket: F) {
+        let Some(mut previous) = self.head.as_mut() else {
+            return;
+        };
+        while let Some(mut node) = previous.next.take() {
+            if same_bucket(&mut node.element, &mut previous.element) {
+                previous.next = node.next.take();
+                self.size -= 1;
+            } else {
+                previous = previous.next.insert(node);
+            }
         }
     }
 
+    //Remove consecutive elements that map to the same key
+    pub fn dedup_by_key<K: PartialEq, F: FnMut(&mut T) -> K>(&mut self, mut key: F) {
+        self.dedup_by(|a, b| key(a) == key(b))
+    }
+
+    //Immutable access to the element in the chosen index
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.iter().nth(index)
+    }
+
+    //Mutable access to the element in the chosen index
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.iter_mut().nth(index)
+    }
+
+    //Lazily remove the elements matching the predicate, returning them in order
+    //Elements that were not reached yet stay in the list if the iterator is dropped early
+    pub fn drain_filter<F: FnMut(&mut T) -> bool>(&mut self, pred: F) -> DrainFilter<'_, T, F> {
+        DrainFilter {
+            link: Some(&mut self.head),
+            size: &mut self.size,
+            pred,
+        }
+    }
+
+    //Stable bottom-up merge sort, nodes are relinked instead of moved
+    //Runs of width 1, 2, 4... are merged pairwise, so no extra space is needed
+    pub fn sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, mut compare: F) {
+        let list = RecountOnPanic(self);
+        let mut width = 1;
+        while width < list.0.size {
+            let mut rest = list.0.head.take();
+            let mut tail = &mut list.0.head;
+            while rest.is_some() {
+                let mut left = rest;
+                let mut right = split_link(&mut left, width);
+                rest = split_link(&mut right, width);
+                tail = merge_links(left, right, &mut compare, tail);
+            }
+            width *= 2;
+        }
+    }
+
+    //Stable sort comparing the keys of the elements
+    pub fn sort_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, mut key: F) {
+        self.sort_by(|a, b| key(a).cmp(&key(b)))
+    }
+
     //Immutable peek at head
     pub fn peek(&self) -> Option<&T> {
         self.head.as_ref().map(|node| &node.element)
@@ -109,21 +233,35 @@ impl<T: PartialEq> List<T> {
     // iter: returns an immutable iterator without modifying the linked list
     // iter_mut: returns a mutable iterator without modifying the linked list
 
-    pub fn into_iter(self) -> IntoIter<T> {
-        IntoIter(self)
-    }
-
-    pub fn iter(&self) -> Iter<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
         Iter {
             next: self.head.as_deref(),
         }
     }
 
-    pub fn iter_mut(&mut self) -> IterMut<T> {
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
         IterMut {
             next: self.head.as_deref_mut(),
         }
     }
+}
+
+impl<T: PartialEq> List<T> {
+    //Check if the element is in the list
+    pub fn contains(&self, element: &T) -> bool {
+        self.iter().any(|item| item == element)
+    }
+
+    //Remove the first node holding the element
+    pub fn remove_first(&mut self, element: &T) -> Option<T> {
+        let index = self.iter().position(|item| item == element)?;
+        self.delete_at(index).ok()
+    }
+
+    //Remove consecutive repeated elements
+    pub fn dedup(&mut self) {
+        self.dedup_by(|a, b| a == b)
+    }
 
     //Find element using iterator
     //TODO Test performance against functions on search moduleç
@@ -143,9 +281,79 @@ impl<T: PartialEq> List<T> {
     }
 }
 
+impl<T: Ord> List<T> {
+    //Stable sort in ascending order
+    pub fn sort(&mut self) {
+        self.sort_by(|a, b| a.cmp(b))
+    }
+
+    //Merge another sorted list into this sorted one, equal elements of self go first
+    pub fn merge_sorted(&mut self, mut other: Self) {
+        self.size += other.size;
+        other.size = 0;
+        let list = RecountOnPanic(self);
+        let left = list.0.head.take();
+        merge_links(
+            left,
+            other.head.take(),
+            &mut |a, b| a.cmp(b),
+            &mut list.0.head,
+        );
+    }
+}
+
+//Nodes held in locals while a comparator panics are dropped on unwind,
+//so count what is still linked to keep len right
+struct RecountOnPanic<'a, T>(&'a mut List<T>);
+
+impl<T> Drop for RecountOnPanic<'_, T> {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            self.0.size = self.0.iter().count();
+        }
+    }
+}
+
+//Cut the chain after len nodes and return the remainder
+fn split_link<T>(link: &mut Link<T>, len: usize) -> Link<T> {
+    let mut target = link;
+    for _ in 0..len {
+        match target {
+            Some(node) => target = &mut node.next,
+            None => return None,
+        }
+    }
+    target.take()
+}
+
+//Relink two sorted chains behind tail, taking from left on ties to keep the merge stable
+//Returns the link after the last merged node so the next merge can follow it
+fn merge_links<'a, T, F: FnMut(&T, &T) -> Ordering>(
+    mut left: Link<T>,
+    mut right: Link<T>,
+    compare: &mut F,
+    mut tail: &'a mut Link<T>,
+) -> &'a mut Link<T> {
+    while let (Some(l), Some(r)) = (left.as_ref(), right.as_ref()) {
+        let source = if compare(&r.element, &l.element) == Ordering::Less {
+            &mut right
+        } else {
+            &mut left
+        };
+        let mut node = source.take().expect("both chains are not empty");
+        *source = node.next.take();
+        tail = &mut tail.insert(node).next;
+    }
+    *tail = left.or(right);
+    while let Some(node) = tail {
+        tail = &mut node.next;
+    }
+    tail
+}
+
 //Implementation of 3 iterators
 pub struct IntoIter<T>(List<T>);
-impl<T: PartialEq> Iterator for IntoIter<T> {
+impl<T> Iterator for IntoIter<T> {
     type Item = T;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -182,6 +390,120 @@ impl<'a, T> Iterator for IterMut<'a, T> {
     }
 }
 
+pub struct DrainFilter<'a, T, F: FnMut(&mut T) -> bool> {
+    link: Option<&'a mut Link<T>>,
+    size: &'a mut usize,
+    pred: F,
+}
+impl<T, F: FnMut(&mut T) -> bool> Iterator for DrainFilter<'_, T, F> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(link) = self.link.take() {
+            let node = link.as_mut()?;
+            if (self.pred)(&mut node.element) {
+                let mut node = link.take().expect("node was just visited");
+                *link = node.next.take();
+                *self.size -= 1;
+                self.link = Some(link);
+                return Some(node.element);
+            }
+            self.link = link.as_mut().map(|node| &mut node.next);
+        }
+        None
+    }
+}
+
+// Implementation of the std collection traits
+// Collecting and extending append at the tail, so a list collects back in iteration order
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T> Extend<T> for List<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let mut tail = &mut self.head;
+        while let Some(node) = tail {
+            tail = &mut node.next;
+        }
+        for element in iter {
+            let node = tail.insert(Box::new(Node {
+                element,
+                next: None,
+            }));
+            tail = &mut node.next;
+            self.size += 1;
+        }
+    }
+}
+
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a List<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut List<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+// Cloned node by node, a derived Clone would recurse once per node
+impl<T: Clone> Clone for List<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: PartialEq> PartialEq for List<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.size == other.size && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for List<T> {}
+
+impl<T: Hash> Hash for List<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.size);
+        for element in self.iter() {
+            element.hash(state);
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
 // Custom implementation of Drop for the linked list
 impl<T> Drop for List<T> {
     fn drop(&mut self) {
@@ -195,6 +517,7 @@ impl<T> Drop for List<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::basic_data_structures::test_utils::hash_of;
 
     #[test]
     fn test_create_list() {
@@ -272,10 +595,8 @@ mod tests {
     fn test_find_at_list() {
         let mut list_a: List<i32> = List::new();
 
-        let mut counter = 0;
-        for _ in 0..=1000 {
+        for counter in 0..=1000 {
             list_a.push(counter);
-            counter += 1;
         }
 
         assert_eq!(list_a.find(563), Some(vec![1000 - 563]));
@@ -320,4 +641,405 @@ mod tests {
         //Panics because list is consumed by iterator
         //list.push(1);
     }
+
+    #[test]
+    fn test_default_and_collect_list() {
+        let list: List<i32> = List::default();
+        assert!(list.is_empty());
+
+        let list: List<i32> = (1..=3).collect();
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![&1, &2, &3]);
+    }
+
+    #[test]
+    fn test_extend_list() {
+        let mut list: List<i32> = List::new();
+        list.push(2);
+        list.push(1);
+
+        list.extend(vec![3, 4]);
+        assert_eq!(list.len(), 4);
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![&1, &2, &3, &4]);
+
+        list.extend(Vec::new());
+        assert_eq!(list.len(), 4);
+    }
+
+    #[test]
+    fn test_for_loops_list() {
+        let mut list: List<i32> = (1..=3).collect();
+
+        for x in &mut list {
+            *x += 10;
+        }
+
+        let mut seen = Vec::new();
+        for x in &list {
+            seen.push(*x);
+        }
+        assert_eq!(seen, vec![11, 12, 13]);
+
+        let mut owned = Vec::new();
+        for x in list {
+            owned.push(x);
+        }
+        assert_eq!(owned, vec![11, 12, 13]);
+    }
+
+    #[test]
+    fn test_clone_eq_hash_list() {
+        let list_a: List<String> = ["a", "b", "c"].iter().map(|s| s.to_string()).collect();
+        let mut list_b = list_a.clone();
+        assert_eq!(list_a, list_b);
+        assert_eq!(hash_of(&list_a), hash_of(&list_b));
+
+        list_b.pop();
+        assert_ne!(list_a, list_b);
+        assert_ne!(hash_of(&list_a), hash_of(&list_b));
+    }
+
+    #[test]
+    fn test_clone_long_list() {
+        let list: List<u32> = (0..100_000).collect();
+        let cloned = list.clone();
+        assert_eq!(cloned.len(), 100_000);
+        assert_eq!(list, cloned);
+    }
+
+    #[test]
+    fn test_debug_list() {
+        let list: List<i32> = (1..=3).collect();
+        assert_eq!(format!("{:?}", list), "[1, 2, 3]");
+        assert_eq!(format!("{:?}", List::<i32>::new()), "[]");
+    }
+
+    #[test]
+    fn test_insert_at_out_of_bounds_list() {
+        let mut list: List<i32> = (1..=3).collect();
+
+        assert_eq!(list.insert_at(3, 4), Ok(()));
+        assert_eq!(list.insert_at(5, 6), Err(IndexError { index: 5, len: 4 }));
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![&1, &2, &3, &4]);
+    }
+
+    #[test]
+    fn test_delete_at_out_of_bounds_list() {
+        let mut list: List<i32> = (1..=3).collect();
+
+        assert_eq!(list.delete_at(2), Ok(3));
+        assert_eq!(list.delete_at(2), Err(IndexError { index: 2, len: 2 }));
+        assert_eq!(list.delete_at(0), Ok(1));
+        assert_eq!(list.len(), 1);
+
+        let mut empty: List<i32> = List::new();
+        assert_eq!(empty.delete_at(0), Err(IndexError { index: 0, len: 0 }));
+    }
+
+    #[test]
+    fn test_append_list() {
+        let mut list: List<i32> = (1..=3).collect();
+        let mut other: List<i32> = (4..=6).collect();
+
+        list.append(&mut other);
+        assert_eq!(list.len(), 6);
+        assert!(other.is_empty());
+        assert_eq!(other.head, None);
+        assert_eq!(
+            list.iter().copied().collect::<Vec<_>>(),
+            vec![1, 2, 3, 4, 5, 6]
+        );
+
+        let mut empty: List<i32> = List::new();
+        empty.append(&mut list);
+        assert_eq!(empty.len(), 6);
+        assert_eq!(list.len(), 0);
+        empty.append(&mut list);
+        assert_eq!(empty.len(), 6);
+    }
+
+    #[test]
+    fn test_split_off_list() {
+        let mut list: List<i32> = (1..=5).collect();
+
+        let tail = list.split_off(2).expect("index is within bounds");
+        assert_eq!(list.len(), 2);
+        assert_eq!(tail.len(), 3);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(tail.iter().copied().collect::<Vec<_>>(), vec![3, 4, 5]);
+
+        let rest = list.split_off(2).expect("index is within bounds");
+        assert!(rest.is_empty());
+        assert_eq!(list.len(), 2);
+
+        let all = list.split_off(0).expect("index is within bounds");
+        assert!(list.is_empty());
+        assert_eq!(all.len(), 2);
+
+        assert_eq!(
+            list.split_off(1).unwrap_err(),
+            IndexError { index: 1, len: 0 }
+        );
+    }
+
+    #[test]
+    fn test_reverse_list() {
+        let mut list: List<i32> = (1..=4).collect();
+        list.reverse();
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![4, 3, 2, 1]);
+        assert_eq!(list.len(), 4);
+        assert_eq!(list.pop(), Some(4));
+
+        let mut empty: List<i32> = List::new();
+        empty.reverse();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_retain_list() {
+        let mut list: List<i32> = (1..=10).collect();
+        list.retain(|x| x % 3 != 0);
+        assert_eq!(list.len(), 7);
+        assert_eq!(
+            list.iter().copied().collect::<Vec<_>>(),
+            vec![1, 2, 4, 5, 7, 8, 10]
+        );
+
+        // the tail is still reachable after removing nodes
+        list.extend([11]);
+        assert_eq!(list.len(), 8);
+        assert_eq!(list.get(7), Some(&11));
+
+        list.retain(|_| false);
+        assert!(list.is_empty());
+        assert_eq!(list.head, None);
+    }
+
+    #[test]
+    fn test_dedup_list() {
+        let mut list: List<i32> = [1, 1, 2, 3, 3, 3, 1, 4, 4].into_iter().collect();
+        list.dedup();
+        assert_eq!(list.len(), 5);
+        assert_eq!(
+            list.iter().copied().collect::<Vec<_>>(),
+            vec![1, 2, 3, 1, 4]
+        );
+
+        let mut list: List<i32> = [10, 11, 20, 21, 22, 30].into_iter().collect();
+        list.dedup_by_key(|x| *x / 10);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![10, 20, 30]);
+
+        let mut empty: List<i32> = List::new();
+        empty.dedup();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_remove_first_contains_list() {
+        let mut list: List<i32> = [1, 2, 3, 2].into_iter().collect();
+
+        assert!(list.contains(&2));
+        assert_eq!(list.remove_first(&2), Some(2));
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 3, 2]);
+
+        assert_eq!(list.remove_first(&5), None);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.remove_first(&2), Some(2));
+        assert!(!list.contains(&2));
+    }
+
+    #[test]
+    fn test_get_list() {
+        let mut list: List<i32> = (1..=3).collect();
+
+        assert_eq!(list.get(0), Some(&1));
+        assert_eq!(list.get(2), Some(&3));
+        assert_eq!(list.get(3), None);
+
+        if let Some(x) = list.get_mut(1) {
+            *x = 20;
+        }
+        assert_eq!(list.get_mut(3), None);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 20, 3]);
+    }
+
+    #[test]
+    fn test_drain_filter_list() {
+        let mut list: List<i32> = (1..=10).collect();
+
+        let evens: Vec<i32> = list.drain_filter(|x| *x % 2 == 0).collect();
+        assert_eq!(evens, vec![2, 4, 6, 8, 10]);
+        assert_eq!(list.len(), 5);
+        assert_eq!(
+            list.iter().copied().collect::<Vec<_>>(),
+            vec![1, 3, 5, 7, 9]
+        );
+
+        // dropping the iterator early leaves the rest untouched
+        {
+            let mut drain = list.drain_filter(|x| {
+                *x *= 10;
+                *x > 20
+            });
+            assert_eq!(drain.next(), Some(30));
+        }
+        assert_eq!(list.len(), 4);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![10, 5, 7, 9]);
+
+        assert_eq!(list.drain_filter(|_| true).count(), 4);
+        assert!(list.is_empty());
+        assert_eq!(list.head, None);
+    }
+
+    #[test]
+    fn test_sort_list() {
+        let mut list: List<i32> = [5, 1, 4, 2, 3, 2].into_iter().collect();
+        list.sort();
+        assert_eq!(list.len(), 6);
+        assert_eq!(
+            list.iter().copied().collect::<Vec<_>>(),
+            vec![1, 2, 2, 3, 4, 5]
+        );
+
+        list.sort_by(|a, b| b.cmp(a));
+        assert_eq!(
+            list.iter().copied().collect::<Vec<_>>(),
+            vec![5, 4, 3, 2, 2, 1]
+        );
+
+        let mut empty: List<i32> = List::new();
+        empty.sort();
+        assert!(empty.is_empty());
+
+        let mut single: List<i32> = (1..=1).collect();
+        single.sort();
+        assert_eq!(single.pop(), Some(1));
+    }
+
+    #[test]
+    fn test_sort_long_list() {
+        // odd length so the last run of every pass has no partner
+        let mut seed: u64 = 42;
+        let mut values: Vec<u32> = (0..10_001)
+            .map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                (seed >> 33) as u32 % 1000
+            })
+            .collect();
+        let mut list: List<u32> = values.iter().copied().collect();
+
+        list.sort();
+        values.sort();
+        assert_eq!(list.len(), values.len());
+        assert!(list.iter().eq(values.iter()));
+
+        // the tail is still reachable after relinking
+        list.extend([1000]);
+        assert_eq!(list.get(10_001), Some(&1000));
+    }
+
+    #[test]
+    fn test_sort_by_key_is_stable_list() {
+        let mut list: List<(i32, char)> = [(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd'), (0, 'e')]
+            .into_iter()
+            .collect();
+        list.sort_by_key(|&(key, _)| key);
+        assert_eq!(
+            list.iter().map(|&(_, tag)| tag).collect::<String>(),
+            "ebdac"
+        );
+    }
+
+    // Ordered by key only, the tag tells equal keys apart
+    #[derive(Debug, Clone, Copy)]
+    struct Tagged(i32, char);
+
+    impl PartialEq for Tagged {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 == other.0
+        }
+    }
+
+    impl Eq for Tagged {}
+
+    impl PartialOrd for Tagged {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Tagged {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.0.cmp(&other.0)
+        }
+    }
+
+    #[test]
+    fn test_merge_sorted_list() {
+        // the tags of other sort first, so only a stable merge puts 3b after 3a
+        let mut list: List<Tagged> = [Tagged(1, 'a'), Tagged(3, 'a'), Tagged(5, 'a')]
+            .into_iter()
+            .collect();
+        let other: List<Tagged> = [Tagged(0, 'Z'), Tagged(3, 'Z'), Tagged(3, 'Y')]
+            .into_iter()
+            .collect();
+
+        list.merge_sorted(other);
+        assert_eq!(list.len(), 6);
+        assert_eq!(
+            list.iter()
+                .map(|&Tagged(key, tag)| (key, tag))
+                .collect::<Vec<_>>(),
+            vec![(0, 'Z'), (1, 'a'), (3, 'a'), (3, 'Z'), (3, 'Y'), (5, 'a')]
+        );
+
+        list.merge_sorted(List::new());
+        assert_eq!(list.len(), 6);
+
+        let mut empty: List<i32> = List::new();
+        empty.merge_sorted((1..=3).collect());
+        assert_eq!(empty.len(), 3);
+        assert_eq!(empty.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_sort_panic_keeps_len_list() {
+        let mut list: List<i32> = (0..100).rev().collect();
+        let mut calls = 0;
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            list.sort_by(|a, b| {
+                calls += 1;
+                if calls > 150 {
+                    panic!("comparator gave up");
+                }
+                a.cmp(b)
+            })
+        }));
+        assert!(result.is_err());
+        assert_eq!(list.len(), list.iter().count());
+
+        // merge_sorted compares through Ord, which panics on a tie here
+        #[derive(PartialEq, Eq)]
+        struct NoTies(i32);
+        impl PartialOrd for NoTies {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+        impl Ord for NoTies {
+            fn cmp(&self, other: &Self) -> Ordering {
+                assert_ne!(self.0, other.0, "tie");
+                self.0.cmp(&other.0)
+            }
+        }
+
+        let mut list: List<NoTies> = [1, 3, 5].into_iter().map(NoTies).collect();
+        let other: List<NoTies> = [0, 3, 6].into_iter().map(NoTies).collect();
+        let result =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| list.merge_sorted(other)));
+        assert!(result.is_err());
+        assert_eq!(list.len(), list.iter().count());
+    }
 }
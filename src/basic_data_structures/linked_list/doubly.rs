@@ -0,0 +1,1139 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::mem;
+use std::ptr::NonNull;
+
+// Nodes are owned by the list through raw pointers, so both neighbours can point at them
+type Link<T> = Option<NonNull<Node<T>>>;
+// First node, last node and length of a chain detached from any list
+type Chain<T> = (NonNull<Node<T>>, NonNull<Node<T>>, usize);
+
+pub struct DoublyLinkedList<T> {
+    size: usize,
+    head: Link<T>,
+    tail: Link<T>,
+    // Tells the drop checker that the list owns boxed nodes
+    marker: PhantomData<Box<Node<T>>>,
+}
+
+struct Node<T> {
+    element: T,
+    prev: Link<T>,
+    next: Link<T>,
+}
+
+impl<T> DoublyLinkedList<T> {
+    //Constructor
+    pub fn new() -> Self {
+        Self {
+            size: 0,
+            head: None,
+            tail: None,
+            marker: PhantomData,
+        }
+    }
+
+    //Check empty
+    pub fn is_empty(&self) -> bool {
+        0 == self.size
+    }
+
+    //Check len
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    //Remove every element
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    //Push to head
+    pub fn push_front(&mut self, element: T) {
+        let node = Self::new_node(element);
+        // SAFETY: node was just allocated and head, if any, is a live node of this list
+        unsafe {
+            (*node.as_ptr()).next = self.head;
+            match self.head {
+                Some(head) => (*head.as_ptr()).prev = Some(node),
+                None => self.tail = Some(node),
+            }
+        }
+        self.head = Some(node);
+        self.size += 1;
+    }
+
+    //Push to tail
+    pub fn push_back(&mut self, element: T) {
+        let node = Self::new_node(element);
+        // SAFETY: node was just allocated and tail, if any, is a live node of this list
+        unsafe {
+            (*node.as_ptr()).prev = self.tail;
+            match self.tail {
+                Some(tail) => (*tail.as_ptr()).next = Some(node),
+                None => self.head = Some(node),
+            }
+        }
+        self.tail = Some(node);
+        self.size += 1;
+    }
+
+    //Pop from head
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.head.map(|node| {
+            // SAFETY: head is a live node of this list, unlinked before being freed
+            unsafe {
+                self.unlink_node(node);
+                Box::from_raw(node.as_ptr()).element
+            }
+        })
+    }
+
+    //Pop from tail
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.tail.map(|node| {
+            // SAFETY: tail is a live node of this list, unlinked before being freed
+            unsafe {
+                self.unlink_node(node);
+                Box::from_raw(node.as_ptr()).element
+            }
+        })
+    }
+
+    //Immutable peek at head and tail
+    pub fn peek_front(&self) -> Option<&T> {
+        // SAFETY: the node lives as long as the shared borrow of the list
+        self.head.map(|node| unsafe { &(*node.as_ptr()).element })
+    }
+
+    pub fn peek_back(&self) -> Option<&T> {
+        // SAFETY: the node lives as long as the shared borrow of the list
+        self.tail.map(|node| unsafe { &(*node.as_ptr()).element })
+    }
+
+    //Mutable peek at head and tail
+    pub fn peek_front_mut(&mut self) -> Option<&mut T> {
+        // SAFETY: the mutable borrow of the list makes this the only reference
+        self.head
+            .map(|node| unsafe { &mut (*node.as_ptr()).element })
+    }
+
+    pub fn peek_back_mut(&mut self) -> Option<&mut T> {
+        // SAFETY: the mutable borrow of the list makes this the only reference
+        self.tail
+            .map(|node| unsafe { &mut (*node.as_ptr()).element })
+    }
+
+    // Implementation of iteration for the linked list.
+    // into_iter: makes the linked list an iterator by consuming it
+    // iter: returns an immutable iterator without modifying the linked list
+    // iter_mut: returns a mutable iterator without modifying the linked list
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            head: self.head,
+            tail: self.tail,
+            len: self.size,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            head: self.head,
+            tail: self.tail,
+            len: self.size,
+            marker: PhantomData,
+        }
+    }
+
+    // Cursors start on the head or the tail, or on the "ghost" position
+    // between tail and head when the list is empty
+    pub fn cursor_front(&self) -> Cursor<'_, T> {
+        Cursor {
+            index: 0,
+            current: self.head,
+            list: self,
+        }
+    }
+
+    pub fn cursor_back(&self) -> Cursor<'_, T> {
+        Cursor {
+            index: self.size.saturating_sub(1),
+            current: self.tail,
+            list: self,
+        }
+    }
+
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            index: 0,
+            current: self.head,
+            list: self,
+        }
+    }
+
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            index: self.size.saturating_sub(1),
+            current: self.tail,
+            list: self,
+        }
+    }
+
+    fn new_node(element: T) -> NonNull<Node<T>> {
+        NonNull::from(Box::leak(Box::new(Node {
+            element,
+            prev: None,
+            next: None,
+        })))
+    }
+
+    // Takes node out of the chain without freeing it
+    // SAFETY: node must be a live node of this list
+    unsafe fn unlink_node(&mut self, node: NonNull<Node<T>>) {
+        let Node { prev, next, .. } = *node.as_ptr();
+        match prev {
+            Some(prev) => (*prev.as_ptr()).next = next,
+            None => self.head = next,
+        }
+        match next {
+            Some(next) => (*next.as_ptr()).prev = prev,
+            None => self.tail = prev,
+        }
+        self.size -= 1;
+    }
+
+    // Links the detached chain first..=last of len nodes between prev and next
+    // SAFETY: prev and next must be adjacent live nodes of this list, or None at the ends,
+    // and the chain must not belong to any list
+    unsafe fn splice_nodes(
+        &mut self,
+        prev: Link<T>,
+        next: Link<T>,
+        first: NonNull<Node<T>>,
+        last: NonNull<Node<T>>,
+        len: usize,
+    ) {
+        match prev {
+            Some(prev) => (*prev.as_ptr()).next = Some(first),
+            None => self.head = Some(first),
+        }
+        match next {
+            Some(next) => (*next.as_ptr()).prev = Some(last),
+            None => self.tail = Some(last),
+        }
+        (*first.as_ptr()).prev = prev;
+        (*last.as_ptr()).next = next;
+        self.size += len;
+    }
+
+    // Moves the nodes after node into a new list, at is the number of nodes kept
+    // A None node means the whole list is moved
+    // SAFETY: node must be a live node of this list at position at - 1
+    unsafe fn split_off_after_node(&mut self, node: Link<T>, at: usize) -> Self {
+        let Some(node) = node else {
+            return mem::take(self);
+        };
+        let second_head = (*node.as_ptr()).next.take();
+        let second_tail = match second_head {
+            Some(second_head) => {
+                (*second_head.as_ptr()).prev = None;
+                self.tail
+            }
+            None => None,
+        };
+        let second = Self {
+            size: self.size - at,
+            head: second_head,
+            tail: second_tail,
+            marker: PhantomData,
+        };
+        self.tail = Some(node);
+        self.size = at;
+        second
+    }
+
+    // Moves the nodes before node into a new list, at is the number of nodes moved
+    // A None node means the whole list is moved
+    // SAFETY: node must be a live node of this list at position at
+    unsafe fn split_off_before_node(&mut self, node: Link<T>, at: usize) -> Self {
+        let Some(node) = node else {
+            return mem::take(self);
+        };
+        let first_tail = (*node.as_ptr()).prev.take();
+        let first_head = match first_tail {
+            Some(first_tail) => {
+                (*first_tail.as_ptr()).next = None;
+                self.head
+            }
+            None => None,
+        };
+        let first = Self {
+            size: at,
+            head: first_head,
+            tail: first_tail,
+            marker: PhantomData,
+        };
+        self.head = Some(node);
+        self.size -= at;
+        first
+    }
+
+    // Hands over the whole chain, leaving nothing for Drop to free
+    fn detach_all(mut self) -> Option<Chain<T>> {
+        let head = self.head.take()?;
+        let tail = self.tail.take()?;
+        Some((head, tail, mem::replace(&mut self.size, 0)))
+    }
+}
+
+// Cursor over a list that can move both ways but not modify it
+// Besides the elements it can sit on a "ghost" position between the tail and the head
+pub struct Cursor<'a, T: 'a> {
+    index: usize,
+    current: Link<T>,
+    list: &'a DoublyLinkedList<T>,
+}
+
+impl<T> Clone for Cursor<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            index: self.index,
+            current: self.current,
+            list: self.list,
+        }
+    }
+}
+
+impl<'a, T> Cursor<'a, T> {
+    //Position of the current element, None on the ghost position
+    pub fn index(&self) -> Option<usize> {
+        self.current.map(|_| self.index)
+    }
+
+    //Move towards the tail, from the tail onto the ghost and from the ghost onto the head
+    pub fn move_next(&mut self) {
+        match self.current {
+            // SAFETY: current is a live node of the borrowed list
+            Some(current) => unsafe {
+                self.current = (*current.as_ptr()).next;
+                self.index += 1;
+            },
+            None => {
+                self.current = self.list.head;
+                self.index = 0;
+            }
+        }
+    }
+
+    //Move towards the head, from the head onto the ghost and from the ghost onto the tail
+    pub fn move_prev(&mut self) {
+        match self.current {
+            // SAFETY: current is a live node of the borrowed list
+            Some(current) => unsafe {
+                self.current = (*current.as_ptr()).prev;
+                self.index = self.index.checked_sub(1).unwrap_or(self.list.size);
+            },
+            None => {
+                self.current = self.list.tail;
+                self.index = self.list.size.saturating_sub(1);
+            }
+        }
+    }
+
+    //Get ref to the current element
+    pub fn current(&self) -> Option<&'a T> {
+        // SAFETY: the node lives as long as the shared borrow of the list
+        self.current
+            .map(|node| unsafe { &(*node.as_ptr()).element })
+    }
+
+    //Get ref to the element move_next would land on
+    pub fn peek_next(&self) -> Option<&'a T> {
+        // SAFETY: current and its neighbours live as long as the shared borrow of the list
+        unsafe {
+            let next = match self.current {
+                Some(current) => (*current.as_ptr()).next,
+                None => self.list.head,
+            };
+            next.map(|node| &(*node.as_ptr()).element)
+        }
+    }
+
+    //Get ref to the element move_prev would land on
+    pub fn peek_prev(&self) -> Option<&'a T> {
+        // SAFETY: current and its neighbours live as long as the shared borrow of the list
+        unsafe {
+            let prev = match self.current {
+                Some(current) => (*current.as_ptr()).prev,
+                None => self.list.tail,
+            };
+            prev.map(|node| &(*node.as_ptr()).element)
+        }
+    }
+}
+
+// Cursor that can also edit the list around its position, every edit is O(1)
+pub struct CursorMut<'a, T: 'a> {
+    index: usize,
+    current: Link<T>,
+    list: &'a mut DoublyLinkedList<T>,
+}
+
+impl<T> CursorMut<'_, T> {
+    //Position of the current element, None on the ghost position
+    pub fn index(&self) -> Option<usize> {
+        self.current.map(|_| self.index)
+    }
+
+    //Move towards the tail, from the tail onto the ghost and from the ghost onto the head
+    pub fn move_next(&mut self) {
+        match self.current {
+            // SAFETY: current is a live node of the borrowed list
+            Some(current) => unsafe {
+                self.current = (*current.as_ptr()).next;
+                self.index += 1;
+            },
+            None => {
+                self.current = self.list.head;
+                self.index = 0;
+            }
+        }
+    }
+
+    //Move towards the head, from the head onto the ghost and from the ghost onto the tail
+    pub fn move_prev(&mut self) {
+        match self.current {
+            // SAFETY: current is a live node of the borrowed list
+            Some(current) => unsafe {
+                self.current = (*current.as_ptr()).prev;
+                self.index = self.index.checked_sub(1).unwrap_or(self.list.size);
+            },
+            None => {
+                self.current = self.list.tail;
+                self.index = self.list.size.saturating_sub(1);
+            }
+        }
+    }
+
+    //Get mutable ref to the current element
+    pub fn current(&mut self) -> Option<&mut T> {
+        // SAFETY: the borrow of the cursor keeps the node alive and unaliased
+        self.current
+            .map(|node| unsafe { &mut (*node.as_ptr()).element })
+    }
+
+    //Get mutable ref to the element move_next would land on
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        // SAFETY: the borrow of the cursor keeps the node alive and unaliased
+        unsafe {
+            let next = match self.current {
+                Some(current) => (*current.as_ptr()).next,
+                None => self.list.head,
+            };
+            next.map(|node| &mut (*node.as_ptr()).element)
+        }
+    }
+
+    //Get mutable ref to the element move_prev would land on
+    pub fn peek_prev(&mut self) -> Option<&mut T> {
+        // SAFETY: the borrow of the cursor keeps the node alive and unaliased
+        unsafe {
+            let prev = match self.current {
+                Some(current) => (*current.as_ptr()).prev,
+                None => self.list.tail,
+            };
+            prev.map(|node| &mut (*node.as_ptr()).element)
+        }
+    }
+
+    //Read only cursor at the same position, usable while this one is borrowed
+    pub fn as_cursor(&self) -> Cursor<'_, T> {
+        Cursor {
+            index: self.index,
+            current: self.current,
+            list: self.list,
+        }
+    }
+
+    //Insert after the current element, or at the head when on the ghost
+    pub fn insert_after(&mut self, element: T) {
+        let node = DoublyLinkedList::new_node(element);
+        // SAFETY: current and its next neighbour are adjacent nodes of the list
+        unsafe {
+            let next = match self.current {
+                Some(current) => (*current.as_ptr()).next,
+                None => self.list.head,
+            };
+            self.list.splice_nodes(self.current, next, node, node, 1);
+        }
+        if self.current.is_none() {
+            self.index = self.list.size;
+        }
+    }
+
+    //Insert before the current element, or at the tail when on the ghost
+    pub fn insert_before(&mut self, element: T) {
+        let node = DoublyLinkedList::new_node(element);
+        // SAFETY: current and its prev neighbour are adjacent nodes of the list
+        unsafe {
+            let prev = match self.current {
+                Some(current) => (*current.as_ptr()).prev,
+                None => self.list.tail,
+            };
+            self.list.splice_nodes(prev, self.current, node, node, 1);
+        }
+        self.index += 1;
+    }
+
+    //Remove the current element and move onto the next one
+    pub fn remove_current(&mut self) -> Option<T> {
+        let node = self.current?;
+        // SAFETY: current is a live node of the list, unlinked before being freed
+        unsafe {
+            self.current = (*node.as_ptr()).next;
+            self.list.unlink_node(node);
+            Some(Box::from_raw(node.as_ptr()).element)
+        }
+    }
+
+    //Move every element of list in after the current element, or at the head on the ghost
+    pub fn splice_after(&mut self, list: DoublyLinkedList<T>) {
+        let Some((first, last, len)) = list.detach_all() else {
+            return;
+        };
+        // SAFETY: the chain was detached from its list and current and next are adjacent
+        unsafe {
+            let next = match self.current {
+                Some(current) => (*current.as_ptr()).next,
+                None => self.list.head,
+            };
+            self.list.splice_nodes(self.current, next, first, last, len);
+        }
+        if self.current.is_none() {
+            self.index = self.list.size;
+        }
+    }
+
+    //Move every element of list in before the current element, or at the tail on the ghost
+    pub fn splice_before(&mut self, list: DoublyLinkedList<T>) {
+        let Some((first, last, len)) = list.detach_all() else {
+            return;
+        };
+        // SAFETY: the chain was detached from its list and prev and current are adjacent
+        unsafe {
+            let prev = match self.current {
+                Some(current) => (*current.as_ptr()).prev,
+                None => self.list.tail,
+            };
+            self.list.splice_nodes(prev, self.current, first, last, len);
+        }
+        self.index += len;
+    }
+
+    //Split off everything after the current element, or the whole list on the ghost
+    pub fn split_after(&mut self) -> DoublyLinkedList<T> {
+        let kept = if self.current.is_some() {
+            self.index + 1
+        } else {
+            self.index = 0;
+            0
+        };
+        // SAFETY: current is a live node of the list at position kept - 1
+        unsafe { self.list.split_off_after_node(self.current, kept) }
+    }
+
+    //Split off everything before the current element, or the whole list on the ghost
+    pub fn split_before(&mut self) -> DoublyLinkedList<T> {
+        let moved = mem::replace(&mut self.index, 0);
+        // SAFETY: current is a live node of the list at position moved
+        unsafe { self.list.split_off_before_node(self.current, moved) }
+    }
+}
+
+//Implementation of 3 iterators
+pub struct IntoIter<T>(DoublyLinkedList<T>);
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.size, Some(self.0.size))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> FusedIterator for IntoIter<T> {}
+
+pub struct Iter<'a, T: 'a> {
+    head: Link<T>,
+    tail: Link<T>,
+    len: usize,
+    marker: PhantomData<&'a Node<T>>,
+}
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.head.map(|node| {
+            // SAFETY: the node lives as long as the shared borrow of the list
+            let node = unsafe { &*node.as_ptr() };
+            self.len -= 1;
+            self.head = node.next;
+            &node.element
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.tail.map(|node| {
+            // SAFETY: the node lives as long as the shared borrow of the list
+            let node = unsafe { &*node.as_ptr() };
+            self.len -= 1;
+            self.tail = node.prev;
+            &node.element
+        })
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Self { ..*self }
+    }
+}
+
+pub struct IterMut<'a, T: 'a> {
+    head: Link<T>,
+    tail: Link<T>,
+    len: usize,
+    marker: PhantomData<&'a mut Node<T>>,
+}
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.head.map(|node| {
+            // SAFETY: len keeps the two ends from handing out the same node twice
+            let node = unsafe { &mut *node.as_ptr() };
+            self.len -= 1;
+            self.head = node.next;
+            &mut node.element
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.tail.map(|node| {
+            // SAFETY: len keeps the two ends from handing out the same node twice
+            let node = unsafe { &mut *node.as_ptr() };
+            self.len -= 1;
+            self.tail = node.prev;
+            &mut node.element
+        })
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+// The raw pointers opt out of Send and Sync, the list is as thread safe as a Box<T>
+unsafe impl<T: Send> Send for DoublyLinkedList<T> {}
+unsafe impl<T: Sync> Sync for DoublyLinkedList<T> {}
+unsafe impl<T: Sync> Send for Iter<'_, T> {}
+unsafe impl<T: Sync> Sync for Iter<'_, T> {}
+unsafe impl<T: Send> Send for IterMut<'_, T> {}
+unsafe impl<T: Sync> Sync for IterMut<'_, T> {}
+unsafe impl<T: Sync> Send for Cursor<'_, T> {}
+unsafe impl<T: Sync> Sync for Cursor<'_, T> {}
+unsafe impl<T: Send> Send for CursorMut<'_, T> {}
+unsafe impl<T: Sync> Sync for CursorMut<'_, T> {}
+
+// Implementation of the std collection traits
+impl<T> Default for DoublyLinkedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FromIterator<T> for DoublyLinkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T> Extend<T> for DoublyLinkedList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for element in iter {
+            self.push_back(element);
+        }
+    }
+}
+
+impl<T> IntoIterator for DoublyLinkedList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a DoublyLinkedList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut DoublyLinkedList<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+impl<T: Clone> Clone for DoublyLinkedList<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: PartialEq> PartialEq for DoublyLinkedList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.size == other.size && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for DoublyLinkedList<T> {}
+
+impl<T: Hash> Hash for DoublyLinkedList<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.size);
+        for element in self.iter() {
+            element.hash(state);
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for DoublyLinkedList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T> Drop for DoublyLinkedList<T> {
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn to_vec(list: &DoublyLinkedList<i32>) -> Vec<i32> {
+        list.iter().copied().collect()
+    }
+
+    // Walks the prev links too, so a broken back pointer shows up
+    fn check_links(list: &DoublyLinkedList<i32>) {
+        let forward = to_vec(list);
+        let mut backward: Vec<i32> = list.iter().rev().copied().collect();
+        backward.reverse();
+        assert_eq!(forward, backward);
+        assert_eq!(forward.len(), list.len());
+    }
+
+    #[test]
+    fn test_push_pop_both_ends_doubly() {
+        let mut list = DoublyLinkedList::new();
+        assert!(list.is_empty());
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.pop_back(), None);
+
+        list.push_back(2);
+        list.push_front(1);
+        list.push_back(3);
+        assert_eq!(list.len(), 3);
+        assert_eq!(to_vec(&list), vec![1, 2, 3]);
+        check_links(&list);
+
+        assert_eq!(list.pop_back(), Some(3));
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.pop_front(), Some(2));
+        assert_eq!(list.pop_back(), None);
+        assert!(list.is_empty());
+
+        list.push_front(4);
+        assert_eq!(list.peek_front(), list.peek_back());
+    }
+
+    #[test]
+    fn test_peek_doubly() {
+        let mut list: DoublyLinkedList<i32> = (1..=3).collect();
+        assert_eq!(list.peek_front(), Some(&1));
+        assert_eq!(list.peek_back(), Some(&3));
+
+        *list.peek_front_mut().unwrap() = 10;
+        *list.peek_back_mut().unwrap() = 30;
+        assert_eq!(to_vec(&list), vec![10, 2, 30]);
+
+        list.clear();
+        assert_eq!(list.peek_front(), None);
+        assert_eq!(list.peek_back_mut(), None);
+    }
+
+    #[test]
+    fn test_iterators_doubly() {
+        let mut list: DoublyLinkedList<i32> = (1..=5).collect();
+
+        let mut iter = list.iter();
+        assert_eq!(iter.len(), 5);
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&5));
+        assert_eq!(iter.clone().collect::<Vec<_>>(), vec![&2, &3, &4]);
+        assert_eq!(iter.next_back(), Some(&4));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next(), Some(&3));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+
+        for (x, y) in list.iter_mut().rev().zip(0..) {
+            *x += y * 10;
+        }
+        assert_eq!(to_vec(&list), vec![41, 32, 23, 14, 5]);
+
+        assert_eq!(
+            list.into_iter().rev().collect::<Vec<_>>(),
+            vec![5, 14, 23, 32, 41]
+        );
+    }
+
+    #[test]
+    fn test_cursor_moves_doubly() {
+        let list: DoublyLinkedList<i32> = (1..=3).collect();
+
+        let mut cursor = list.cursor_front();
+        assert_eq!(cursor.current(), Some(&1));
+        assert_eq!(cursor.index(), Some(0));
+        assert_eq!(cursor.peek_prev(), None);
+        assert_eq!(cursor.peek_next(), Some(&2));
+
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&3));
+        assert_eq!(cursor.index(), Some(2));
+
+        // past the tail sits the ghost, then the head again
+        cursor.move_next();
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.index(), None);
+        assert_eq!(cursor.peek_next(), Some(&1));
+        assert_eq!(cursor.peek_prev(), Some(&3));
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&1));
+        assert_eq!(cursor.index(), Some(0));
+
+        cursor.move_prev();
+        assert_eq!(cursor.current(), None);
+        cursor.move_prev();
+        assert_eq!(cursor.current(), Some(&3));
+        assert_eq!(cursor.index(), Some(2));
+
+        let back = list.cursor_back();
+        assert_eq!(back.current(), Some(&3));
+        assert_eq!(back.index(), Some(2));
+
+        let empty: DoublyLinkedList<i32> = DoublyLinkedList::new();
+        let mut cursor = empty.cursor_front();
+        assert_eq!(cursor.current(), None);
+        cursor.move_next();
+        cursor.move_prev();
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.peek_next(), None);
+    }
+
+    #[test]
+    fn test_cursor_insert_doubly() {
+        let mut list: DoublyLinkedList<i32> = vec![2, 4].into_iter().collect();
+
+        let mut cursor = list.cursor_front_mut();
+        cursor.insert_before(1);
+        assert_eq!(cursor.index(), Some(1));
+        assert_eq!(cursor.current(), Some(&mut 2));
+        cursor.insert_after(3);
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 3));
+        assert_eq!(cursor.index(), Some(2));
+
+        // on the ghost, insert_after goes to the head and insert_before to the tail
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.index(), None);
+        cursor.insert_after(0);
+        cursor.insert_before(5);
+        assert_eq!(cursor.peek_next(), Some(&mut 0));
+        assert_eq!(cursor.peek_prev(), Some(&mut 5));
+        cursor.move_prev();
+        assert_eq!(cursor.index(), Some(5));
+
+        assert_eq!(to_vec(&list), vec![0, 1, 2, 3, 4, 5]);
+        check_links(&list);
+    }
+
+    #[test]
+    fn test_cursor_remove_doubly() {
+        let mut list: DoublyLinkedList<i32> = (1..=4).collect();
+
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(2));
+        assert_eq!(cursor.current(), Some(&mut 3));
+        assert_eq!(cursor.index(), Some(1));
+
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(4));
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.remove_current(), None);
+
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(1));
+        assert_eq!(cursor.index(), Some(0));
+        assert_eq!(cursor.as_cursor().current(), Some(&3));
+
+        assert_eq!(to_vec(&list), vec![3]);
+        check_links(&list);
+    }
+
+    #[test]
+    fn test_cursor_splice_doubly() {
+        let mut list: DoublyLinkedList<i32> = vec![1, 5].into_iter().collect();
+
+        let mut cursor = list.cursor_front_mut();
+        cursor.splice_after((2..=3).collect());
+        assert_eq!(cursor.current(), Some(&mut 1));
+        cursor.move_next();
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 5));
+        cursor.splice_before(vec![4].into_iter().collect());
+        assert_eq!(cursor.index(), Some(4));
+
+        cursor.splice_before(DoublyLinkedList::new());
+        cursor.splice_after(DoublyLinkedList::new());
+        assert_eq!(cursor.index(), Some(4));
+
+        // ghost splices go to the head and the tail
+        cursor.move_next();
+        cursor.splice_after(vec![-1, 0].into_iter().collect());
+        cursor.splice_before(vec![6, 7].into_iter().collect());
+        assert_eq!(cursor.index(), None);
+        cursor.move_prev();
+        assert_eq!(cursor.index(), Some(8));
+
+        assert_eq!(to_vec(&list), vec![-1, 0, 1, 2, 3, 4, 5, 6, 7]);
+        check_links(&list);
+    }
+
+    #[test]
+    fn test_cursor_split_doubly() {
+        let mut list: DoublyLinkedList<i32> = (1..=6).collect();
+
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        cursor.move_next();
+        let after = cursor.split_after();
+        assert_eq!(cursor.current(), Some(&mut 3));
+        assert_eq!(cursor.peek_next(), None);
+        let before = cursor.split_before();
+        assert_eq!(cursor.index(), Some(0));
+        assert_eq!(cursor.peek_prev(), None);
+
+        assert_eq!(to_vec(&list), vec![3]);
+        assert_eq!(to_vec(&before), vec![1, 2]);
+        assert_eq!(to_vec(&after), vec![4, 5, 6]);
+        check_links(&list);
+        check_links(&before);
+        check_links(&after);
+
+        // splitting at the ends leaves an empty side
+        let mut cursor = list.cursor_front_mut();
+        assert!(cursor.split_before().is_empty());
+        assert!(cursor.split_after().is_empty());
+
+        // on the ghost the whole list is split off
+        let mut list: DoublyLinkedList<i32> = (1..=3).collect();
+        let mut cursor = list.cursor_back_mut();
+        cursor.move_next();
+        let all = cursor.split_after();
+        assert_eq!(cursor.index(), None);
+        cursor.move_next();
+        assert_eq!(cursor.current(), None);
+        assert_eq!(to_vec(&all), vec![1, 2, 3]);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn test_cursor_walk_edit_doubly() {
+        // drop every odd number and duplicate every even one
+        let mut list: DoublyLinkedList<i32> = (1..=10).collect();
+        let mut cursor = list.cursor_front_mut();
+        while let Some(&mut x) = cursor.current() {
+            if x % 2 == 1 {
+                cursor.remove_current();
+            } else {
+                cursor.insert_after(x);
+                cursor.move_next();
+                cursor.move_next();
+            }
+        }
+        assert_eq!(to_vec(&list), vec![2, 2, 4, 4, 6, 6, 8, 8, 10, 10]);
+        check_links(&list);
+    }
+
+    #[test]
+    fn test_drop_doubly() {
+        struct Counted<'a>(&'a Cell<usize>);
+        impl Drop for Counted<'_> {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let drops = Cell::new(0);
+        {
+            let mut list = DoublyLinkedList::new();
+            for _ in 0..10 {
+                list.push_back(Counted(&drops));
+            }
+            let mut cursor = list.cursor_front_mut();
+            cursor.move_next();
+            drop(cursor.remove_current());
+            let split = cursor.split_after();
+            assert_eq!(drops.get(), 1);
+            drop(split);
+            assert_eq!(drops.get(), 8);
+
+            let mut iter = list.into_iter();
+            drop(iter.next());
+            assert_eq!(drops.get(), 9);
+        }
+        assert_eq!(drops.get(), 10);
+    }
+
+    #[test]
+    fn test_traits_doubly() {
+        let list: DoublyLinkedList<String> = ["a", "b"].iter().map(|s| s.to_string()).collect();
+        let mut cloned = list.clone();
+        assert_eq!(list, cloned);
+        assert_eq!(format!("{:?}", list), r#"["a", "b"]"#);
+
+        cloned.push_front(String::from("z"));
+        assert_ne!(list, cloned);
+
+        let mut default: DoublyLinkedList<i32> = DoublyLinkedList::default();
+        default.extend(vec![1, 2]);
+        for x in &mut default {
+            *x *= 2;
+        }
+        assert_eq!((&default).into_iter().sum::<i32>(), 6);
+    }
+
+    #[test]
+    fn test_send_sync_doubly() {
+        fn assert_send<T: Send>() {}
+        fn assert_sync<T: Sync>() {}
+        assert_send::<DoublyLinkedList<i32>>();
+        assert_sync::<DoublyLinkedList<i32>>();
+        assert_send::<Iter<'_, i32>>();
+        assert_sync::<Iter<'_, i32>>();
+        assert_send::<IterMut<'_, i32>>();
+        assert_sync::<IterMut<'_, i32>>();
+        assert_send::<Cursor<'_, i32>>();
+        assert_sync::<Cursor<'_, i32>>();
+        assert_send::<CursorMut<'_, i32>>();
+        assert_sync::<CursorMut<'_, i32>>();
+
+        // a list built on one thread is read and edited on others
+        let mut list: DoublyLinkedList<String> = (0..4).map(|i| i.to_string()).collect();
+        std::thread::scope(|s| {
+            let a = s.spawn(|| list.iter().map(|x| x.len()).sum::<usize>());
+            let b = s.spawn(|| list.cursor_back().current().cloned());
+            assert_eq!(a.join().unwrap(), 4);
+            assert_eq!(b.join().unwrap(), Some("3".to_string()));
+        });
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                let mut cursor = list.cursor_front_mut();
+                cursor.insert_before("x".to_string());
+                cursor.remove_current();
+            });
+        });
+        let moved = std::thread::spawn(move || {
+            list.push_back("4".to_string());
+            list
+        })
+        .join()
+        .unwrap();
+        assert_eq!(
+            moved.iter().collect::<Vec<_>>(),
+            vec!["x", "1", "2", "3", "4"]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_long_list_doubly() {
+        let mut list: DoublyLinkedList<u32> = (0..100_000).collect();
+        assert_eq!(list.len(), 100_000);
+        assert_eq!(list.clone(), list);
+        list.clear();
+        assert!(list.is_empty());
+    }
+}
@@ -1,4 +1,10 @@
+pub mod blocking_queue;
 pub mod deque;
 pub mod linked_list;
+pub mod policy;
 pub mod queue;
+mod ring;
 pub mod stack;
+
+#[cfg(test)]
+mod test_utils;
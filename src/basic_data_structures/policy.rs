@@ -0,0 +1,17 @@
+// What a bounded Queue or Deque does with a new element once it is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum OverflowPolicy {
+    // Hand the new element back inside a CapacityError
+    #[default]
+    Reject,
+    // Evict the oldest element to make room and hand it back, like a ring log
+    // A Deque evicts the opposite end to the one pushed to instead, which is only the
+    // oldest element while every push goes to the same end
+    // A capacity of 0 leaves nothing to evict, so the new element is rejected
+    OverwriteOldest,
+    // Double the capacity and keep the new element
+    Grow,
+    // Wait for a consumer to make room; only a BlockingQueue can wait,
+    // the single threaded containers reject like Reject does
+    Block,
+}
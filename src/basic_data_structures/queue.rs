@@ -1,51 +1,139 @@
-#[derive(Debug)]
+use super::policy::OverflowPolicy;
+use super::ring;
+use crate::error::CapacityError;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::FusedIterator;
+use std::slice;
+
+#[derive(Clone)]
 pub struct Queue<T> {
     cap: usize,
-    data: Vec<T>,
+    // index of the most recently enqueued element
+    head: usize,
+    // index one past the next element to be dequeued
+    tail: usize,
+    len: usize,
+    data: Vec<Option<T>>,
+    policy: OverflowPolicy,
 }
 
 impl<T> Queue<T> {
-    //Constructor
+    //Constructor, rejects new elements once full
     pub fn new(size: usize) -> Self {
+        Self::with_policy(size, OverflowPolicy::Reject)
+    }
+
+    //Constructor with a chosen behaviour for when the queue is full
+    pub fn with_policy(size: usize, policy: OverflowPolicy) -> Self {
+        let mut data = Vec::with_capacity(size);
+        data.resize_with(size, || None);
         Self {
             cap: size,
-            data: Vec::with_capacity(size),
+            head: 0,
+            tail: 0,
+            len: 0,
+            data,
+            policy,
+        }
+    }
+
+    //Constructor for a queue that grows as needed, never rejecting an element
+    pub fn unbounded() -> Self {
+        Self::with_policy(0, OverflowPolicy::Grow)
+    }
+
+    //Check overflow policy
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    //Check capacity, the bound of a bounded queue or the allocated size of an unbounded one
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    //Make room for at least additional more elements, growing at least double to stay amortised O(1)
+    //Only an unbounded queue reallocates, a bounded one already holds every slot of its bound
+    pub fn reserve(&mut self, additional: usize) {
+        let required = self.len + additional;
+        if self.policy == OverflowPolicy::Grow && required > self.cap {
+            self.reallocate(required.max(self.cap * 2));
+        }
+    }
+
+    //Drop unused slots of an unbounded queue
+    //A bounded one keeps its bound, set_capacity is the way to change it
+    pub fn shrink_to_fit(&mut self) {
+        if self.policy == OverflowPolicy::Grow && self.cap > self.len {
+            self.reallocate(self.len);
         }
     }
 
+    //Change the capacity, panics if it is smaller than the current len
+    pub fn set_capacity(&mut self, cap: usize) {
+        assert!(
+            cap >= self.len,
+            "capacity {} is smaller than the len {}",
+            cap,
+            self.len
+        );
+        self.reallocate(cap);
+    }
+
     //Check empty
     pub fn is_empty(&self) -> bool {
         0 == self.len()
     }
 
-    //Check full
+    //Check full, an unbounded queue never is
     pub fn is_full(&self) -> bool {
-        self.len() == self.cap
+        self.policy != OverflowPolicy::Grow && self.len() == self.cap
     }
 
     //Check length
     pub fn len(&self) -> usize {
-        self.data.len()
+        self.len
     }
 
     //Clear Queue
     pub fn clear(&mut self) {
-        self.data = Vec::with_capacity(self.cap);
+        for slot in self.data.iter_mut() {
+            *slot = None;
+        }
+        self.head = 0;
+        self.tail = 0;
+        self.len = 0;
     }
 
     //Insert element
-    pub fn enqueue(&mut self, val: T) -> Result<(), &str> {
+    //When full the overflow policy applies, an evicted element is returned in Ok
+    pub fn enqueue(&mut self, val: T) -> Result<Option<T>, CapacityError<T>> {
+        let mut evicted = None;
         if self.len() == self.cap {
-            return Err("No space available");
+            match self.policy {
+                OverflowPolicy::Reject | OverflowPolicy::Block => {
+                    return Err(CapacityError::new(val));
+                }
+                OverflowPolicy::OverwriteOldest if self.cap == 0 => {
+                    return Err(CapacityError::new(val));
+                }
+                OverflowPolicy::OverwriteOldest => evicted = self.dequeue(),
+                OverflowPolicy::Grow => self.reallocate((self.cap * 2).max(1)),
+            }
         }
-        self.data.insert(0, val);
-        Ok(())
+        self.head = self.wrap_sub(self.head, 1);
+        self.data[self.head] = Some(val);
+        self.len += 1;
+        Ok(evicted)
     }
 
     //Pop out values
     pub fn dequeue(&mut self) -> Option<T> {
-        if self.len() > 0 {
-            self.data.pop()
+        if !self.is_empty() {
+            self.tail = self.wrap_sub(self.tail, 1);
+            self.len -= 1;
+            self.data[self.tail].take()
         } else {
             None
         }
@@ -53,189 +141,366 @@ impl<T> Queue<T> {
 
     //Peek Queue
     pub fn peek(&self) -> Option<&T> {
-        self.data.last()
+        if !self.is_empty() {
+            self.data[self.wrap_sub(self.tail, 1)].as_ref()
+        } else {
+            None
+        }
+    }
+
+    // Ring buffer index arithmetic, only called while cap > 0
+    fn wrap_sub(&self, index: usize, offset: usize) -> usize {
+        (index + self.cap - offset) % self.cap
+    }
+
+    // Move the elements into a buffer of new_cap slots, unwrapped to start at slot 0
+    fn reallocate(&mut self, new_cap: usize) {
+        self.data = ring::reallocate(&mut self.data, self.head, self.len, new_cap);
+        self.cap = new_cap;
+        self.head = 0;
+        self.tail = if self.len == new_cap { 0 } else { self.len };
+    }
+
+    fn as_slices(&self) -> (&[Option<T>], &[Option<T>]) {
+        ring::as_slices(&self.data, self.head, self.len)
+    }
+
+    fn as_mut_slices(&mut self) -> (&mut [Option<T>], &mut [Option<T>]) {
+        ring::as_mut_slices(&mut self.data, self.head, self.len)
     }
 
     // Implementation of iterations for a queue
     // into_iter(): queue modified and became a iterator
     // iter(): queue unmodified and get a immutable iterator
     // iter_mut(): queue unmodified and get a mutable iterator
-    pub fn into_iter(self) -> IntoIter<T> {
-        IntoIter(self)
-    }
-
-    pub fn iter(&self) -> Iter<T> {
-        let mut iterator = Iter { stack: Vec::new() };
-        for item in self.data.iter() {
-            iterator.stack.push(item);
+    pub fn iter(&self) -> Iter<'_, T> {
+        let (first, second) = self.as_slices();
+        Iter {
+            first: first.iter(),
+            second: second.iter(),
         }
-        iterator
     }
 
-    pub fn iter_mut(&mut self) -> IterMut<T> {
-        let mut iterator = IterMut { stack: Vec::new() };
-        for item in self.data.iter_mut() {
-            iterator.stack.push(item);
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        let (first, second) = self.as_mut_slices();
+        IterMut {
+            first: first.iter_mut(),
+            second: second.iter_mut(),
         }
-        iterator
     }
 }
 
 // Implementation of 3 iterations
+// All of them run from the newest element to the oldest and can be reversed
 pub struct IntoIter<T>(Queue<T>);
-impl<T: Clone> Iterator for IntoIter<T> {
+impl<T> Iterator for IntoIter<T> {
     type Item = T;
     fn next(&mut self) -> Option<Self::Item> {
-        if !self.0.is_empty() {
-            Some(self.0.data.remove(0))
+        let queue = &mut self.0;
+        if !queue.is_empty() {
+            let val = queue.data[queue.head].take();
+            queue.head = (queue.head + 1) % queue.cap;
+            queue.len -= 1;
+            val
         } else {
             None
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len(), Some(self.0.len()))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.dequeue()
+    }
 }
 
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> FusedIterator for IntoIter<T> {}
+
 pub struct Iter<'a, T: 'a> {
-    stack: Vec<&'a T>,
+    first: slice::Iter<'a, Option<T>>,
+    second: slice::Iter<'a, Option<T>>,
 }
 impl<'a, T> Iterator for Iter<'a, T> {
     type Item = &'a T;
     fn next(&mut self) -> Option<Self::Item> {
-        if 0 != self.stack.len() {
-            Some(self.stack.remove(0))
-        } else {
-            None
-        }
+        self.first
+            .next()
+            .or_else(|| self.second.next())
+            .and_then(Option::as_ref)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.first.len() + self.second.len();
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.second
+            .next_back()
+            .or_else(|| self.first.next_back())
+            .and_then(Option::as_ref)
     }
 }
 
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
 pub struct IterMut<'a, T: 'a> {
-    stack: Vec<&'a mut T>,
+    first: slice::IterMut<'a, Option<T>>,
+    second: slice::IterMut<'a, Option<T>>,
 }
 impl<'a, T> Iterator for IterMut<'a, T> {
     type Item = &'a mut T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if 0 != self.stack.len() {
-            Some(self.stack.remove(0))
-        } else {
-            None
+        self.first
+            .next()
+            .or_else(|| self.second.next())
+            .and_then(Option::as_mut)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.first.len() + self.second.len();
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.second
+            .next_back()
+            .or_else(|| self.first.next_back())
+            .and_then(Option::as_mut)
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+// Implementation of the std collection traits
+// Collecting and extending enqueue in iteration order, so the first item is dequeued first
+// Default is unbounded, and extending goes through the overflow policy like enqueue does:
+// a full queue grows or drops what it evicts, and panics rather than go past a Reject or Block bound
+impl<T> Default for Queue<T> {
+    fn default() -> Self {
+        Self::unbounded()
+    }
+}
+
+impl<T> FromIterator<T> for Queue<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut queue = Self::default();
+        queue.extend(iter);
+        queue
+    }
+}
+
+impl<T> Extend<T> for Queue<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        if self.policy == OverflowPolicy::Grow {
+            self.reserve(iter.size_hint().0);
+        }
+        for val in iter {
+            if self.enqueue(val).is_err() {
+                panic!("extend overflowed a full queue of capacity {}", self.cap);
+            }
+        }
+    }
+}
+
+impl<T> IntoIterator for Queue<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Queue<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut Queue<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+// Equality and hashing only look at the elements, not at the capacity
+impl<T: PartialEq> PartialEq for Queue<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for Queue<T> {}
+
+impl<T: Hash> Hash for Queue<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        for val in self.iter() {
+            val.hash(state);
         }
     }
 }
 
+impl<T: fmt::Debug> fmt::Debug for Queue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::basic_data_structures::test_utils::hash_of;
+
+    // Builds a queue whose elements read newest to oldest as `items`
+    fn queue_from(cap: usize, items: &[i32]) -> Queue<i32> {
+        let mut queue = Queue::new(cap);
+        for &item in items.iter().rev() {
+            queue.enqueue(item).expect("is not at full cap");
+        }
+        queue
+    }
 
     #[test]
     fn test_create_queue() {
         let queue: Queue<i32> = Queue::new(5);
         assert_eq!(queue.cap, 5);
-        assert_eq!(queue.data.len(), 0);
+        assert_eq!(queue.len, 0);
+        assert_eq!(queue.data.len(), 5);
     }
 
     #[test]
     fn test_check_empty_queue() {
-        let queue: Queue<i32> = Queue {
-            cap: 5,
-            data: vec![],
-        };
+        let queue: Queue<i32> = Queue::new(5);
         assert!(queue.is_empty());
     }
 
     #[test]
     fn test_check_full_queue() {
-        let queue_a: Queue<i32> = Queue {
-            cap: 5,
-            data: vec![],
-        };
+        let queue_a = queue_from(5, &[]);
         assert!(!queue_a.is_full());
 
-        let queue_b: Queue<i32> = Queue {
-            cap: 5,
-            data: vec![1, 1, 1, 1, 1],
-        };
+        let queue_b = queue_from(5, &[1, 1, 1, 1, 1]);
         assert!(queue_b.is_full());
+
+        let queue_c: Queue<i32> = Queue::new(0);
+        assert!(queue_c.is_full());
     }
 
     #[test]
     fn test_clear_queue() {
-        let mut queue: Queue<i32> = Queue {
-            cap: 3,
-            data: vec![1, 2, 3],
-        };
+        let mut queue = queue_from(3, &[1, 2, 3]);
         queue.clear();
         assert!(queue.is_empty());
+        assert!(queue.data.iter().all(Option::is_none));
     }
 
     #[test]
     fn test_enqueue() {
-        let mut queue_a: Queue<i32> = Queue {
-            cap: 10,
-            data: vec![1, 2, 3],
-        };
+        let mut queue_a = queue_from(10, &[1, 2, 3]);
         queue_a.enqueue(4).expect("is not at full cap");
-        assert_eq!(queue_a.data, vec![4, 1, 2, 3]);
+        assert_eq!(queue_a.iter().collect::<Vec<_>>(), vec![&4, &1, &2, &3]);
 
-        let mut queue_b: Queue<i32> = Queue {
-            cap: 3,
-            data: vec![1, 2, 3],
-        };
+        let mut queue_b = queue_from(3, &[1, 2, 3]);
         match queue_b.enqueue(4) {
             Ok(..) => (),
             Err(e) => eprintln!("{}", e),
         }
-        assert_eq!(queue_b.data.len(), 3);
+        assert_eq!(queue_b.len(), 3);
     }
 
     #[test]
     fn test_dequeue() {
-        let mut queue_a: Queue<i32> = Queue {
-            cap: 3,
-            data: vec![1, 2, 3],
-        };
-        queue_a.dequeue();
-        assert_eq!(queue_a.data, vec![1, 2]);
-
-        let mut queue_b: Queue<i32> = Queue {
-            cap: 5,
-            data: vec![],
-        };
-        queue_b.dequeue();
-        assert_eq!(queue_b.data, vec![]);
+        let mut queue_a = queue_from(3, &[1, 2, 3]);
+        assert_eq!(queue_a.dequeue(), Some(3));
+        assert_eq!(queue_a.iter().collect::<Vec<_>>(), vec![&1, &2]);
+
+        let mut queue_b = queue_from(5, &[]);
+        assert_eq!(queue_b.dequeue(), None);
+        assert!(queue_b.is_empty());
     }
 
     #[test]
     fn test_peek_queue() {
-        let queue_a: Queue<i32> = Queue {
-            cap: 3,
-            data: vec![1, 2, 3],
-        };
+        let queue_a = queue_from(3, &[1, 2, 3]);
         assert_eq!(queue_a.peek(), Some(&3));
 
-        let queue_b: Queue<i32> = Queue {
-            cap: 2,
-            data: vec![],
-        };
+        let queue_b = queue_from(2, &[]);
         assert_eq!(queue_b.peek(), None);
     }
 
     #[test]
     fn test_get_len_queue() {
-        let queue: Queue<i32> = Queue {
-            cap: 10,
-            data: vec![1, 2, 3],
-        };
+        let queue = queue_from(10, &[1, 2, 3]);
 
         assert_eq!(queue.len(), 3)
     }
 
+    #[test]
+    fn test_wraparound_queue() {
+        let mut queue: Queue<i32> = Queue::new(4);
+        for i in 0..4 {
+            queue.enqueue(i).expect("is not at full cap");
+        }
+        assert_eq!(queue.dequeue(), Some(0));
+        assert_eq!(queue.dequeue(), Some(1));
+
+        // head walks past slot 0 and wraps to the end of the buffer
+        queue.enqueue(4).expect("is not at full cap");
+        queue.enqueue(5).expect("is not at full cap");
+        assert!(queue.is_full());
+        assert_eq!(queue.head, 2);
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&5, &4, &3, &2]);
+
+        for x in queue.iter_mut() {
+            *x *= 10;
+        }
+        assert_eq!(queue.dequeue(), Some(20));
+        assert_eq!(queue.dequeue(), Some(30));
+        assert_eq!(queue.dequeue(), Some(40));
+        assert_eq!(queue.dequeue(), Some(50));
+        assert_eq!(queue.dequeue(), None);
+    }
+
+    #[test]
+    fn test_wraparound_many_cycles_queue() {
+        let mut queue: Queue<i32> = Queue::new(3);
+        for i in 0..100 {
+            queue.enqueue(i).expect("is not at full cap");
+            if queue.is_full() {
+                assert_eq!(queue.dequeue(), Some(i - 2));
+            }
+            assert_eq!(queue.peek(), Some(&(i - queue.len() as i32 + 1)));
+        }
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&99, &98]);
+    }
+
     #[test]
     fn test_iter_queue() {
-        let mut queue: Queue<i32> = Queue {
-            cap: 10,
-            data: vec![1, 2, 3],
-        };
+        let mut queue = queue_from(10, &[1, 2, 3]);
 
         let mut iter = queue.iter();
 
@@ -250,10 +515,7 @@ mod tests {
 
     #[test]
     fn test_iter_mut_queue() {
-        let mut queue: Queue<i32> = Queue {
-            cap: 10,
-            data: vec![1, 2, 3],
-        };
+        let mut queue = queue_from(10, &[1, 2, 3]);
 
         let mut iter = queue.iter_mut();
 
@@ -268,10 +530,7 @@ mod tests {
 
     #[test]
     fn test_iter_mut_queue_2() {
-        let mut queue: Queue<i32> = Queue {
-            cap: 10,
-            data: vec![1, 2, 3],
-        };
+        let mut queue = queue_from(10, &[1, 2, 3]);
 
         let iter = queue.iter_mut();
 
@@ -294,10 +553,7 @@ mod tests {
 
     #[test]
     fn test_into_iter_queue() {
-        let queue: Queue<i32> = Queue {
-            cap: 10,
-            data: vec![1, 2, 3],
-        };
+        let queue = queue_from(10, &[1, 2, 3]);
 
         let mut iter = queue.into_iter();
 
@@ -309,4 +565,293 @@ mod tests {
         //Panics because into_iter consumes the queue
         //queue.enqueue(1);
     }
+
+    #[test]
+    fn test_iter_double_ended_queue() {
+        let mut queue: Queue<i32> = Queue::new(4);
+        for i in 0..6 {
+            queue.enqueue(i).expect("is not at full cap");
+            if queue.len() > 3 {
+                queue.dequeue();
+            }
+        }
+        // occupied slots now straddle the end of the buffer
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&5, &4, &3]);
+        assert_eq!(queue.iter().rev().collect::<Vec<_>>(), vec![&3, &4, &5]);
+
+        let mut iter = queue.iter_mut();
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.next_back(), Some(&mut 3));
+        assert_eq!(iter.next(), Some(&mut 5));
+        assert_eq!(iter.len(), 1);
+        assert_eq!(iter.next_back(), Some(&mut 4));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+
+        let mut into_iter = queue.into_iter();
+        assert_eq!(into_iter.len(), 3);
+        assert_eq!(into_iter.next_back(), Some(3));
+        assert_eq!(into_iter.next(), Some(5));
+        assert_eq!(into_iter.next(), Some(4));
+        assert_eq!(into_iter.next(), None);
+    }
+
+    #[test]
+    fn test_into_iter_without_clone_queue() {
+        #[derive(Debug, PartialEq)]
+        struct NoClone(i32);
+
+        let mut queue = Queue::new(2);
+        queue.enqueue(NoClone(1)).expect("is not at full cap");
+        queue.enqueue(NoClone(2)).expect("is not at full cap");
+
+        assert_eq!(
+            queue.into_iter().rev().collect::<Vec<_>>(),
+            vec![NoClone(1), NoClone(2)]
+        );
+    }
+
+    #[test]
+    fn test_default_and_collect_queue() {
+        let queue: Queue<i32> = Queue::default();
+        assert!(queue.is_empty());
+        assert!(!queue.is_full());
+        assert_eq!(queue.policy(), OverflowPolicy::Grow);
+
+        let mut queue: Queue<i32> = (1..=3).collect();
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.peek(), Some(&1));
+        assert_eq!(queue.dequeue(), Some(1));
+    }
+
+    #[test]
+    fn test_extend_grows_queue() {
+        let mut queue = Queue::with_policy(3, OverflowPolicy::Grow);
+        queue.enqueue(1).expect("is not at full cap");
+        queue.enqueue(2).expect("is not at full cap");
+
+        // filter hides the length, so the buffer has to double while extending
+        queue.extend((3..=6).filter(|_| true));
+        assert_eq!(queue.len(), 6);
+        assert!(queue.cap >= 6);
+        assert_eq!(
+            queue.iter().collect::<Vec<_>>(),
+            vec![&6, &5, &4, &3, &2, &1]
+        );
+        for i in 1..=6 {
+            assert_eq!(queue.dequeue(), Some(i));
+        }
+    }
+
+    #[test]
+    fn test_extend_keeps_bound_queue() {
+        let mut queue = queue_from(4, &[2, 1]);
+        queue.extend(3..=4);
+        assert!(queue.is_full());
+        assert_eq!(queue.capacity(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "extend overflowed a full queue of capacity 3")]
+    fn test_extend_past_bound_queue() {
+        let mut queue = queue_from(3, &[2, 1]);
+        queue.extend(3..=4);
+    }
+
+    #[test]
+    fn test_for_loops_queue() {
+        let mut queue = queue_from(3, &[3, 2, 1]);
+
+        for x in &mut queue {
+            *x += 10;
+        }
+
+        let mut seen = Vec::new();
+        for x in &queue {
+            seen.push(*x);
+        }
+        assert_eq!(seen, vec![13, 12, 11]);
+
+        let mut owned = Vec::new();
+        for x in queue {
+            owned.push(x);
+        }
+        assert_eq!(owned, vec![13, 12, 11]);
+    }
+
+    #[test]
+    fn test_clone_eq_hash_queue() {
+        // same elements, but different capacities and buffer layouts
+        let queue_a = queue_from(3, &[3, 2, 1]);
+        let mut queue_b: Queue<i32> = Queue::new(5);
+        queue_b.enqueue(0).expect("is not at full cap");
+        queue_b.extend(1..=3);
+        queue_b.dequeue();
+
+        assert_eq!(queue_a, queue_b);
+        assert_eq!(hash_of(&queue_a), hash_of(&queue_b));
+
+        let mut queue_c = queue_b.clone();
+        assert_eq!(queue_b, queue_c);
+        queue_c.dequeue();
+        assert_ne!(queue_b, queue_c);
+        assert_ne!(hash_of(&queue_b), hash_of(&queue_c));
+    }
+
+    #[test]
+    fn test_debug_queue() {
+        let queue = queue_from(5, &[3, 2, 1]);
+        assert_eq!(format!("{:?}", queue), "[3, 2, 1]");
+        assert_eq!(format!("{:?}", Queue::<i32>::new(2)), "[]");
+    }
+
+    #[test]
+    fn test_enqueue_full_returns_element() {
+        let mut queue: Queue<String> = Queue::new(1);
+        queue
+            .enqueue(String::from("kept"))
+            .expect("is not at full cap");
+
+        let err = queue.enqueue(String::from("rejected")).unwrap_err();
+        assert_eq!(err.to_string(), "No space available");
+        assert_eq!(err.element(), "rejected");
+        assert_eq!(queue.peek().map(String::as_str), Some("kept"));
+    }
+
+    #[test]
+    fn test_overwrite_oldest_policy_queue() {
+        let mut queue = Queue::with_policy(3, OverflowPolicy::OverwriteOldest);
+        assert_eq!(queue.policy(), OverflowPolicy::OverwriteOldest);
+
+        for i in 1..=3 {
+            assert_eq!(queue.enqueue(i), Ok(None));
+        }
+        assert_eq!(queue.enqueue(4), Ok(Some(1)));
+        assert_eq!(queue.enqueue(5), Ok(Some(2)));
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&5, &4, &3]);
+
+        queue.extend(6..=9);
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&9, &8, &7]);
+
+        let mut empty = Queue::with_policy(0, OverflowPolicy::OverwriteOldest);
+        assert_eq!(empty.enqueue(1).unwrap_err().element(), 1);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_grow_policy_queue() {
+        let mut queue = Queue::with_policy(0, OverflowPolicy::Grow);
+        for i in 1..=5 {
+            assert_eq!(queue.enqueue(i), Ok(None));
+        }
+        // capacity doubles 0 -> 1 -> 2 -> 4 -> 8
+        assert_eq!(queue.cap, 8);
+        for i in 1..=5 {
+            assert_eq!(queue.dequeue(), Some(i));
+        }
+    }
+
+    #[test]
+    fn test_reject_and_block_policies_queue() {
+        for policy in [OverflowPolicy::Reject, OverflowPolicy::Block] {
+            let mut queue = Queue::with_policy(1, policy);
+            assert_eq!(queue.enqueue(1), Ok(None));
+            assert_eq!(queue.enqueue(2).unwrap_err().element(), 2);
+            assert_eq!(queue.peek(), Some(&1));
+        }
+        assert_eq!(Queue::<i32>::new(1).policy(), OverflowPolicy::Reject);
+    }
+
+    #[test]
+    fn test_unbounded_queue() {
+        let mut queue = Queue::unbounded();
+        assert_eq!(queue.capacity(), 0);
+
+        for i in 0..1000 {
+            assert_eq!(queue.enqueue(i), Ok(None));
+            assert!(!queue.is_full());
+        }
+        assert_eq!(queue.len(), 1000);
+        assert_eq!(queue.capacity(), 1024);
+        for i in 0..1000 {
+            assert_eq!(queue.dequeue(), Some(i));
+        }
+    }
+
+    #[test]
+    fn test_reserve_queue() {
+        let mut bounded = queue_from(3, &[2, 1]);
+        bounded.reserve(1);
+        assert_eq!(bounded.capacity(), 3);
+        // the bound only changes through set_capacity
+        bounded.reserve(4);
+        assert_eq!(bounded.capacity(), 3);
+        assert_eq!(bounded.iter().collect::<Vec<_>>(), vec![&2, &1]);
+
+        let mut unbounded: Queue<i32> = Queue::unbounded();
+        unbounded.extend(0..4);
+        unbounded.reserve(1);
+        assert_eq!(unbounded.capacity(), 8);
+    }
+
+    #[test]
+    fn test_shrink_to_fit_queue() {
+        // a bounded queue still takes elements up to its original capacity
+        let mut bounded = queue_from(10, &[3, 2, 1]);
+        bounded.shrink_to_fit();
+        assert_eq!(bounded.capacity(), 10);
+        for i in 4..=10 {
+            assert_eq!(bounded.enqueue(i), Ok(None));
+        }
+        assert!(bounded.enqueue(11).is_err());
+
+        let mut ring = Queue::with_policy(4, OverflowPolicy::OverwriteOldest);
+        ring.enqueue(1).expect("is not at full cap");
+        ring.shrink_to_fit();
+        for i in 2..=4 {
+            assert_eq!(ring.enqueue(i), Ok(None));
+        }
+
+        let mut unbounded: Queue<i32> = Queue::unbounded();
+        unbounded.extend(0..5);
+        unbounded.dequeue();
+        unbounded.shrink_to_fit();
+        assert_eq!(unbounded.capacity(), 4);
+        assert_eq!(unbounded.enqueue(5), Ok(None));
+        assert_eq!(
+            unbounded.iter().collect::<Vec<_>>(),
+            vec![&5, &4, &3, &2, &1]
+        );
+    }
+
+    #[test]
+    fn test_set_capacity_queue() {
+        let mut queue: Queue<i32> = Queue::new(2);
+        queue.enqueue(1).expect("is not at full cap");
+        queue.enqueue(2).expect("is not at full cap");
+        queue.dequeue();
+        queue.enqueue(3).expect("is not at full cap");
+
+        // the ring is wrapped, raising the bound has to unwrap it
+        queue.set_capacity(4);
+        assert_eq!(queue.capacity(), 4);
+        assert_eq!(queue.enqueue(4), Ok(None));
+        assert_eq!(queue.enqueue(5), Ok(None));
+        assert!(queue.is_full());
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&5, &4, &3, &2]);
+
+        queue.dequeue();
+        queue.set_capacity(3);
+        assert!(queue.is_full());
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&5, &4, &3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity 1 is smaller than the len 2")]
+    fn test_set_capacity_below_len_queue() {
+        let mut queue = queue_from(2, &[1, 2]);
+        queue.set_capacity(1);
+    }
 }
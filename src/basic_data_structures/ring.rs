@@ -0,0 +1,45 @@
+// Ring buffer helpers shared by Queue and Deque
+// Both keep len occupied slots starting at head, wrapping past the end of data back to slot 0
+
+// Occupied slots from head onwards, then the part that wrapped to slot 0
+pub(super) fn as_slices<T>(
+    data: &[Option<T>],
+    head: usize,
+    len: usize,
+) -> (&[Option<T>], &[Option<T>]) {
+    let cap = data.len();
+    if head + len <= cap {
+        (&data[head..head + len], &[])
+    } else {
+        let (wrapped, from_head) = data.split_at(head);
+        (from_head, &wrapped[..head + len - cap])
+    }
+}
+
+pub(super) fn as_mut_slices<T>(
+    data: &mut [Option<T>],
+    head: usize,
+    len: usize,
+) -> (&mut [Option<T>], &mut [Option<T>]) {
+    let cap = data.len();
+    if head + len <= cap {
+        (&mut data[head..head + len], &mut [])
+    } else {
+        let (wrapped, from_head) = data.split_at_mut(head);
+        (from_head, &mut wrapped[..head + len - cap])
+    }
+}
+
+// Move the elements into a new buffer of new_cap slots, unwrapped to start at slot 0
+pub(super) fn reallocate<T>(
+    data: &mut [Option<T>],
+    head: usize,
+    len: usize,
+    new_cap: usize,
+) -> Vec<Option<T>> {
+    let mut moved = Vec::with_capacity(new_cap);
+    let (first, second) = as_mut_slices(data, head, len);
+    moved.extend(first.iter_mut().chain(second.iter_mut()).map(Option::take));
+    moved.resize_with(new_cap, || None);
+    moved
+}
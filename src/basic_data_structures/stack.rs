@@ -1,4 +1,9 @@
-#[derive(Debug)]
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::{FusedIterator, Rev};
+use std::{slice, vec};
+
+#[derive(Clone)]
 pub struct Stack<T> {
     size: usize,
     data: Vec<T>,
@@ -57,64 +62,158 @@ impl<T> Stack<T> {
     // into_iter(): stack modified and became a iterator
     // iter(): stack unmodified and get a immutable iterator
     // iter_mut(): stack unmodified and get a mutable iterator
-    pub fn into_iter(self) -> IntoIter<T> {
-        IntoIter(self)
-    }
-
-    pub fn iter(&self) -> Iter<T> {
-        let mut iterator = Iter { stack: Vec::new() };
-        for item in self.data.iter() {
-            iterator.stack.push(item);
-        }
-        iterator
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter(self.data.iter().rev())
     }
 
-    pub fn iter_mut(&mut self) -> IterMut<T> {
-        let mut iterator = IterMut { stack: Vec::new() };
-        for item in self.data.iter_mut() {
-            iterator.stack.push(item);
-        }
-        iterator
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut(self.data.iter_mut().rev())
     }
 }
 
 // Implementation of 3 iterations
-pub struct IntoIter<T>(Stack<T>);
-impl<T: Clone> Iterator for IntoIter<T> {
+// All of them run from the top of the stack down and can be reversed
+pub struct IntoIter<T>(Rev<vec::IntoIter<T>>);
+impl<T> Iterator for IntoIter<T> {
     type Item = T;
     fn next(&mut self) -> Option<Self::Item> {
-        if !self.0.is_empty() {
-            self.0.size -= 1;
-            self.0.data.pop()
-        } else {
-            None
-        }
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
     }
 }
 
-pub struct Iter<'a, T: 'a> {
-    stack: Vec<&'a T>,
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
 }
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> FusedIterator for IntoIter<T> {}
+
+pub struct Iter<'a, T: 'a>(Rev<slice::Iter<'a, T>>);
 impl<'a, T> Iterator for Iter<'a, T> {
     type Item = &'a T;
     fn next(&mut self) -> Option<Self::Item> {
-        self.stack.pop()
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
     }
 }
 
-pub struct IterMut<'a, T: 'a> {
-    stack: Vec<&'a mut T>,
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
 }
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+pub struct IterMut<'a, T: 'a>(Rev<slice::IterMut<'a, T>>);
 impl<'a, T> Iterator for IterMut<'a, T> {
     type Item = &'a mut T;
     fn next(&mut self) -> Option<Self::Item> {
-        self.stack.pop()
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+// Implementation of the std collection traits
+// Collecting and extending push in iteration order, so the last item ends on top
+impl<T> Default for Stack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FromIterator<T> for Stack<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut stack = Self::new();
+        stack.extend(iter);
+        stack
+    }
+}
+
+impl<T> Extend<T> for Stack<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.push(val);
+        }
+    }
+}
+
+impl<T> IntoIterator for Stack<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self.data.into_iter().rev())
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Stack<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut Stack<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+impl<T: PartialEq> PartialEq for Stack<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.data == other.data
+    }
+}
+
+impl<T: Eq> Eq for Stack<T> {}
+
+impl<T: Hash> Hash for Stack<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.data.hash(state);
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Stack<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::basic_data_structures::test_utils::hash_of;
 
     #[test]
     fn test_create_stack() {
@@ -270,4 +369,96 @@ mod tests {
         //Panics because into_iter consumes the stack
         //stack.push(1);
     }
+
+    #[test]
+    fn test_iter_double_ended_stack() {
+        let mut stack: Stack<i32> = Stack::new();
+        for i in 1..=4 {
+            stack.push(i);
+        }
+
+        assert_eq!(stack.iter().rev().collect::<Vec<_>>(), vec![&1, &2, &3, &4]);
+
+        let mut iter = stack.iter_mut();
+        assert_eq!(iter.next(), Some(&mut 4));
+        assert_eq!(iter.next_back(), Some(&mut 1));
+        assert_eq!(iter.len(), 2);
+        assert_eq!(iter.next_back(), Some(&mut 2));
+        assert_eq!(iter.next(), Some(&mut 3));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+
+        let mut into_iter = stack.into_iter();
+        assert_eq!(into_iter.len(), 4);
+        assert_eq!(into_iter.next_back(), Some(1));
+        assert_eq!(into_iter.collect::<Vec<_>>(), vec![4, 3, 2]);
+    }
+
+    #[test]
+    fn test_into_iter_without_clone_stack() {
+        #[derive(Debug, PartialEq)]
+        struct NoClone(i32);
+
+        let mut stack = Stack::new();
+        stack.push(NoClone(1));
+        stack.push(NoClone(2));
+
+        assert_eq!(
+            stack.into_iter().collect::<Vec<_>>(),
+            vec![NoClone(2), NoClone(1)]
+        );
+    }
+
+    #[test]
+    fn test_default_and_collect_stack() {
+        let stack: Stack<i32> = Stack::default();
+        assert!(stack.is_empty());
+
+        let stack: Stack<i32> = (1..=3).collect();
+        assert_eq!(stack.get_size(), &3);
+        assert_eq!(stack.peek(), Some(&3));
+
+        let mut stack = stack;
+        stack.extend(vec![4, 5]);
+        assert_eq!(stack.iter().collect::<Vec<_>>(), vec![&5, &4, &3, &2, &1]);
+    }
+
+    #[test]
+    fn test_for_loops_stack() {
+        let mut stack: Stack<i32> = (1..=3).collect();
+
+        for x in &mut stack {
+            *x += 10;
+        }
+
+        let mut seen = Vec::new();
+        for x in &stack {
+            seen.push(*x);
+        }
+        assert_eq!(seen, vec![13, 12, 11]);
+
+        let mut owned = Vec::new();
+        for x in stack {
+            owned.push(x);
+        }
+        assert_eq!(owned, vec![13, 12, 11]);
+    }
+
+    #[test]
+    fn test_clone_eq_hash_stack() {
+        let stack_a: Stack<String> = ["a", "b"].iter().map(|s| s.to_string()).collect();
+        let mut stack_b = stack_a.clone();
+        assert_eq!(stack_a, stack_b);
+        assert_eq!(hash_of(&stack_a), hash_of(&stack_b));
+
+        stack_b.pop();
+        assert_ne!(stack_a, stack_b);
+    }
+
+    #[test]
+    fn test_debug_stack() {
+        let stack: Stack<i32> = (1..=3).collect();
+        assert_eq!(format!("{:?}", stack), "[3, 2, 1]");
+        assert_eq!(format!("{:?}", Stack::<i32>::new()), "[]");
+    }
 }
@@ -0,0 +1,87 @@
+use std::error::Error;
+use std::fmt;
+
+// Returned when a bounded container has no room left, handing the rejected element back
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct CapacityError<T> {
+    element: T,
+}
+
+impl<T> CapacityError<T> {
+    //Constructor
+    pub fn new(element: T) -> Self {
+        Self { element }
+    }
+
+    //Recover the element that could not be inserted
+    pub fn element(self) -> T {
+        self.element
+    }
+}
+
+impl<T> fmt::Display for CapacityError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "No space available")
+    }
+}
+
+// Written by hand so the error can be unwrapped or boxed whatever T is
+impl<T> fmt::Debug for CapacityError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CapacityError").finish_non_exhaustive()
+    }
+}
+
+impl<T> Error for CapacityError<T> {}
+
+// Returned when an index does not point inside a container of length len
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexError {
+    pub index: usize,
+    pub len: usize,
+}
+
+impl fmt::Display for IndexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Index out of bounds: the len is {} but the index is {}",
+            self.len, self.index
+        )
+    }
+}
+
+impl Error for IndexError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capacity_error_returns_element() {
+        let err = CapacityError::new(String::from("rejected"));
+        assert_eq!(err.to_string(), "No space available");
+        assert_eq!(format!("{:?}", err), "CapacityError { .. }");
+        assert_eq!(err.element(), "rejected");
+    }
+
+    #[test]
+    fn test_index_error_display() {
+        let err = IndexError { index: 7, len: 3 };
+        assert_eq!(
+            err.to_string(),
+            "Index out of bounds: the len is 3 but the index is 7"
+        );
+    }
+
+    #[test]
+    fn test_errors_box_into_dyn_error() {
+        struct NotDebug;
+
+        let errors: Vec<Box<dyn Error>> = vec![
+            Box::new(CapacityError::new(NotDebug)),
+            Box::new(IndexError { index: 1, len: 0 }),
+        ];
+        assert_eq!(errors.len(), 2);
+    }
+}
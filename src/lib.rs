@@ -1,3 +1,4 @@
 pub mod basic_data_structures;
+pub mod error;
 pub mod search;
 pub mod sort;
@@ -1,25 +1,57 @@
+use super::partial_order;
+use super::stats::{swap, Observer, Silent};
+use std::cmp::Ordering;
+
 pub fn bubble_sort<T: PartialOrd>(nums: &mut [T]) {
+    bubble_sort_by(nums, partial_order);
+}
+
+pub fn bubble_sort_by<T, F: FnMut(&T, &T) -> Ordering>(nums: &mut [T], mut compare: F) {
+    bubble_sort_observed(nums, &mut compare, &Silent);
+}
+
+pub(crate) fn bubble_sort_observed<T, F, O>(nums: &mut [T], compare: &mut F, observer: &O)
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
     let len = nums.len();
     if len <= 1 {
         return;
     }
 
     for _ in 0..len {
-        let mut compare = false;
+        let mut compared = false;
         for j in 0..len - 1 {
-            if nums[j] > nums[j + 1] {
-                nums.swap(j, j + 1);
-                compare = true;
+            if compare(&nums[j], &nums[j + 1]) == Ordering::Greater {
+                swap(nums, j, j + 1, observer);
+                compared = true;
             }
         }
-        if !compare {
+        if !compared {
             break;
         }
     }
 }
 
-//Linus torvalds would be disgusted with all the nesting
+pub fn bubble_sort_by_key<T, K: PartialOrd, F: FnMut(&T) -> K>(nums: &mut [T], mut key: F) {
+    bubble_sort_by(nums, |a, b| partial_order(&key(a), &key(b)));
+}
+
 pub fn cocktail_sort<T: PartialOrd>(nums: &mut [T]) {
+    cocktail_sort_by(nums, partial_order);
+}
+
+pub fn cocktail_sort_by<T, F: FnMut(&T, &T) -> Ordering>(nums: &mut [T], mut compare: F) {
+    cocktail_sort_observed(nums, &mut compare, &Silent);
+}
+
+//Linus torvalds would be disgusted with all the nesting
+pub(crate) fn cocktail_sort_observed<T, F, O>(nums: &mut [T], compare: &mut F, observer: &O)
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
     let len = nums.len();
     if len <= 1 {
         return;
@@ -31,14 +63,14 @@ pub fn cocktail_sort<T: PartialOrd>(nums: &mut [T]) {
         if bubble {
             bubble = false;
             for j in i..len - i - 1 {
-                if nums[j] > nums[j + 1] {
-                    nums.swap(j, j + 1);
+                if compare(&nums[j], &nums[j + 1]) == Ordering::Greater {
+                    swap(nums, j, j + 1, observer);
                     bubble = true;
                 }
             }
             for j in (i + 1..len - i - 1).rev() {
-                if nums[j] < nums[j - 1] {
-                    nums.swap(j, j - 1);
+                if compare(&nums[j], &nums[j - 1]) == Ordering::Less {
+                    swap(nums, j, j - 1, observer);
                     bubble = true;
                 }
             }
@@ -48,7 +80,23 @@ pub fn cocktail_sort<T: PartialOrd>(nums: &mut [T]) {
     }
 }
 
+pub fn cocktail_sort_by_key<T, K: PartialOrd, F: FnMut(&T) -> K>(nums: &mut [T], mut key: F) {
+    cocktail_sort_by(nums, |a, b| partial_order(&key(a), &key(b)));
+}
+
 pub fn comb_sort<T: PartialOrd>(nums: &mut [T]) {
+    comb_sort_by(nums, partial_order);
+}
+
+pub fn comb_sort_by<T, F: FnMut(&T, &T) -> Ordering>(nums: &mut [T], mut compare: F) {
+    comb_sort_observed(nums, &mut compare, &Silent);
+}
+
+pub(crate) fn comb_sort_observed<T, F, O>(nums: &mut [T], compare: &mut F, observer: &O)
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
     let len = nums.len();
     if len <= 1 {
         return;
@@ -69,8 +117,8 @@ pub fn comb_sort<T: PartialOrd>(nums: &mut [T]) {
 
         let mut i = 0;
         while i + gap < len {
-            if nums[i] > nums[i + gap] {
-                nums.swap(i, i + gap);
+            if compare(&nums[i], &nums[i + gap]) == Ordering::Greater {
+                swap(nums, i, i + gap, observer);
                 swapped = true;
             }
             i += 1;
@@ -82,6 +130,10 @@ pub fn comb_sort<T: PartialOrd>(nums: &mut [T]) {
     }
 }
 
+pub fn comb_sort_by_key<T, K: PartialOrd, F: FnMut(&T) -> K>(nums: &mut [T], mut key: F) {
+    comb_sort_by(nums, |a, b| partial_order(&key(a), &key(b)));
+}
+
 pub fn cant_believe_it_can_sort_asc<T: PartialOrd>(nums: &mut [T]) {
     for i in 0..nums.len() {
         for j in i + 1..nums.len() {
@@ -105,6 +157,7 @@ pub fn cant_believe_it_can_sort_desc<T: PartialOrd>(nums: &mut [T]) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sort::test_utils::check_by_variants;
 
     #[test]
     fn test_bubble_sort_integers() {
@@ -245,4 +298,28 @@ mod tests {
         cant_believe_it_can_sort_desc(&mut nums);
         assert_eq!(nums, []);
     }
+
+    #[test]
+    fn test_bubble_sort_by_variants() {
+        check_by_variants(
+            |nums, compare| bubble_sort_by(nums, compare),
+            |records, key| bubble_sort_by_key(records, key),
+        );
+    }
+
+    #[test]
+    fn test_cocktail_sort_by_variants() {
+        check_by_variants(
+            |nums, compare| cocktail_sort_by(nums, compare),
+            |records, key| cocktail_sort_by_key(records, key),
+        );
+    }
+
+    #[test]
+    fn test_comb_sort_by_variants() {
+        check_by_variants(
+            |nums, compare| comb_sort_by(nums, compare),
+            |records, key| comb_sort_by_key(records, key),
+        );
+    }
 }
@@ -0,0 +1,552 @@
+use super::kway::kway_merge_by;
+use super::partial_order;
+use super::sorter::{Algorithm, Sorter};
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+
+// External merge sort for fixed-width records that don't fit in memory:
+// - the input is read in chunks that fit the memory budget, each chunk is sorted with an in-memory Sorter
+// - every sorted chunk is spilled to a temp file (a run)
+// - runs are merged with kway_merge, in several passes when there are more runs than MAX_FAN_IN
+// With a stable sorter the whole sort is stable, the merge breaks ties by run order
+
+// Turns records into fixed-width bytes and back
+pub trait RecordCodec {
+    type Record;
+
+    //Number of bytes of every encoded record
+    fn width(&self) -> usize;
+
+    //buf is exactly width() bytes
+    fn encode(&self, record: &Self::Record, buf: &mut [u8]);
+
+    //buf is exactly width() bytes
+    fn decode(&self, buf: &[u8]) -> Self::Record;
+}
+
+// u64 stored big-endian, so the files also sort bytewise
+#[derive(Debug, Clone, Copy, Default)]
+pub struct U64Codec;
+
+impl RecordCodec for U64Codec {
+    type Record = u64;
+
+    fn width(&self) -> usize {
+        mem::size_of::<u64>()
+    }
+
+    fn encode(&self, record: &u64, buf: &mut [u8]) {
+        buf.copy_from_slice(&record.to_be_bytes());
+    }
+
+    fn decode(&self, buf: &[u8]) -> u64 {
+        u64::from_be_bytes(buf.try_into().unwrap())
+    }
+}
+
+// Raw records of width bytes, compared as byte strings by sort
+#[derive(Debug, Clone, Copy)]
+pub struct FixedBytes {
+    pub width: usize,
+}
+
+impl RecordCodec for FixedBytes {
+    type Record = Vec<u8>;
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn encode(&self, record: &Vec<u8>, buf: &mut [u8]) {
+        buf.copy_from_slice(record);
+    }
+
+    fn decode(&self, buf: &[u8]) -> Vec<u8> {
+        buf.to_vec()
+    }
+}
+
+// What a sort did, spilled_runs is 0 when the whole input fit in memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ExternalSortStats {
+    pub records: u64,
+    pub spilled_runs: usize,
+    pub merge_passes: usize,
+}
+
+// Budget used by external_sort
+pub const DEFAULT_MEMORY_BUDGET: usize = 64 << 20;
+// Most runs merged at once, bounds the open files
+const MAX_FAN_IN: usize = 64;
+
+pub struct ExternalSorter<C, S = Algorithm> {
+    codec: C,
+    sorter: S,
+    memory_budget: usize,
+    temp_dir: PathBuf,
+}
+
+impl<C: RecordCodec> ExternalSorter<C> {
+    //Sorts chunks with tim_sort, spills to the system temp dir
+    pub fn new(codec: C, memory_budget: usize) -> Self {
+        Self::with_sorter(codec, memory_budget, Algorithm::Tim)
+    }
+}
+
+impl<C: RecordCodec, S: Sorter> ExternalSorter<C, S> {
+    //memory_budget bounds the bytes of records held at once, read and write buffers come on top
+    //So does any scratch the chunk sorter allocates, the default tim_sort takes up to half a chunk more
+    pub fn with_sorter(codec: C, memory_budget: usize, sorter: S) -> Self {
+        Self {
+            codec,
+            sorter,
+            memory_budget,
+            temp_dir: env::temp_dir(),
+        }
+    }
+
+    //Directory the runs are spilled to, they are removed once the sort is done or fails
+    pub fn set_temp_dir(&mut self, dir: impl Into<PathBuf>) {
+        self.temp_dir = dir.into();
+    }
+
+    pub fn sort<R: Read, W: Write>(&self, input: R, output: W) -> io::Result<ExternalSortStats>
+    where
+        C::Record: PartialOrd,
+    {
+        self.sort_by(input, output, partial_order)
+    }
+
+    pub fn sort_by_key<R, W, K, F>(
+        &self,
+        input: R,
+        output: W,
+        mut key: F,
+    ) -> io::Result<ExternalSortStats>
+    where
+        R: Read,
+        W: Write,
+        K: PartialOrd,
+        F: FnMut(&C::Record) -> K,
+    {
+        self.sort_by(input, output, |a, b| partial_order(&key(a), &key(b)))
+    }
+
+    pub fn sort_by<R, W, F>(
+        &self,
+        input: R,
+        output: W,
+        mut compare: F,
+    ) -> io::Result<ExternalSortStats>
+    where
+        R: Read,
+        W: Write,
+        F: FnMut(&C::Record, &C::Record) -> Ordering,
+    {
+        let width = self.codec.width();
+        // a decoded record plus its encoded bytes, an upper bound for most codecs
+        let record_size = mem::size_of::<C::Record>() + width;
+        let chunk_len = self.memory_budget / record_size;
+        if width == 0 || chunk_len < 2 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "memory budget must hold at least two records",
+            ));
+        }
+        // the heap holds one record per merged run
+        let fan_in = chunk_len.min(MAX_FAN_IN);
+
+        let mut input = BufReader::new(input);
+        let mut output = BufWriter::new(output);
+        let mut stats = ExternalSortStats::default();
+        let mut runs = Vec::new();
+        let mut chunk = Vec::with_capacity(chunk_len);
+        let mut buf = vec![0; width];
+        loop {
+            while chunk.len() < chunk_len && read_record(&mut input, &mut buf)? {
+                chunk.push(self.codec.decode(&buf));
+            }
+            if chunk.is_empty() {
+                break;
+            }
+            stats.records += chunk.len() as u64;
+            self.sorter.sort_by(&mut chunk, &mut compare);
+
+            // everything fit in memory, no need to spill
+            if runs.is_empty() && chunk.len() < chunk_len {
+                self.write_records(&chunk, &mut output)?;
+                output.flush()?;
+                return Ok(stats);
+            }
+            let (run, file) = Run::create(&self.temp_dir)?;
+            let mut writer = BufWriter::new(file);
+            self.write_records(&chunk, &mut writer)?;
+            writer.flush()?;
+            runs.push(run);
+            chunk.clear();
+        }
+        stats.spilled_runs = runs.len();
+
+        // Merge consecutive groups so ties still resolve in input order
+        while runs.len() > fan_in {
+            let mut merged = Vec::with_capacity(runs.len().div_ceil(fan_in));
+            for group in runs.chunks(fan_in) {
+                let (run, file) = Run::create(&self.temp_dir)?;
+                let mut writer = BufWriter::new(file);
+                self.merge_runs(group, &mut writer, &mut compare)?;
+                writer.flush()?;
+                merged.push(run);
+            }
+            runs = merged;
+            stats.merge_passes += 1;
+        }
+        if !runs.is_empty() {
+            self.merge_runs(&runs, &mut output, &mut compare)?;
+            stats.merge_passes += 1;
+        }
+        output.flush()?;
+        Ok(stats)
+    }
+
+    fn write_records<W: Write>(&self, records: &[C::Record], output: &mut W) -> io::Result<()> {
+        let mut buf = vec![0; self.codec.width()];
+        for record in records {
+            self.codec.encode(record, &mut buf);
+            output.write_all(&buf)?;
+        }
+        Ok(())
+    }
+
+    // k-way merge of the runs, ties go to the earlier run
+    fn merge_runs<W, F>(&self, runs: &[Run], output: &mut W, compare: &mut F) -> io::Result<()>
+    where
+        W: Write,
+        F: FnMut(&C::Record, &C::Record) -> Ordering,
+    {
+        let error = Cell::new(None);
+        let readers = runs
+            .iter()
+            .map(|run| {
+                Ok(RunReader {
+                    codec: &self.codec,
+                    reader: BufReader::new(File::open(&run.path)?),
+                    buf: vec![0; self.codec.width()],
+                    error: &error,
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let mut buf = vec![0; self.codec.width()];
+        for record in kway_merge_by(readers, |a, b| compare(a, b)) {
+            if let Some(e) = error.take() {
+                return Err(e);
+            }
+            self.codec.encode(&record, &mut buf);
+            output.write_all(&buf)?;
+        }
+        error.take().map_or(Ok(()), Err)
+    }
+}
+
+// Decodes the records of a run, the first read error ends it and is left in error for merge_runs
+struct RunReader<'a, C> {
+    codec: &'a C,
+    reader: BufReader<File>,
+    buf: Vec<u8>,
+    error: &'a Cell<Option<io::Error>>,
+}
+
+impl<C: RecordCodec> Iterator for RunReader<'_, C> {
+    type Item = C::Record;
+
+    fn next(&mut self) -> Option<C::Record> {
+        match read_record(&mut self.reader, &mut self.buf) {
+            Ok(true) => Some(self.codec.decode(&self.buf)),
+            Ok(false) => None,
+            Err(e) => {
+                self.error.set(Some(e));
+                None
+            }
+        }
+    }
+}
+
+//Sort fixed-width records from input into output with the default budget and sorter
+pub fn external_sort<C, R, W>(codec: C, input: R, output: W) -> io::Result<ExternalSortStats>
+where
+    C: RecordCodec,
+    C::Record: PartialOrd,
+    R: Read,
+    W: Write,
+{
+    ExternalSorter::new(codec, DEFAULT_MEMORY_BUDGET).sort(input, output)
+}
+
+//Fill buf with the next record, false at a clean end of input
+fn read_record<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<bool> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "input ends in the middle of a record",
+                ))
+            }
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(true)
+}
+
+// Used to give every run file of this process its own name
+static NEXT_RUN: AtomicUsize = AtomicUsize::new(0);
+
+// A sorted run spilled to disk, the file is removed when the run is dropped
+struct Run {
+    path: PathBuf,
+}
+
+impl Run {
+    //Open a new run file for writing
+    //create_new never truncates or follows an existing path, a taken name moves on to the next id
+    fn create(dir: &Path) -> io::Result<(Self, File)> {
+        loop {
+            let id = NEXT_RUN.fetch_add(1, AtomicOrdering::Relaxed);
+            let path = dir.join(format!("algo-ds-rs-{}-{}.run", process::id(), id));
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => return Ok((Self { path }, file)),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl Drop for Run {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::test_utils::check_stability;
+
+    // (key, id) pairs, the id shows whether equal keys kept their input order
+    struct PairCodec;
+
+    impl RecordCodec for PairCodec {
+        type Record = (u32, u32);
+
+        fn width(&self) -> usize {
+            8
+        }
+
+        fn encode(&self, record: &(u32, u32), buf: &mut [u8]) {
+            buf[..4].copy_from_slice(&record.0.to_le_bytes());
+            buf[4..].copy_from_slice(&record.1.to_le_bytes());
+        }
+
+        fn decode(&self, buf: &[u8]) -> (u32, u32) {
+            let key = u32::from_le_bytes(buf[..4].try_into().unwrap());
+            let id = u32::from_le_bytes(buf[4..].try_into().unwrap());
+            (key, id)
+        }
+    }
+
+    // A fresh temp dir per test, so leftover runs can be spotted
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("algo-ds-rs-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn assert_empty_and_remove(dir: &Path) {
+        assert_eq!(fs::read_dir(dir).unwrap().count(), 0, "runs left behind");
+        fs::remove_dir(dir).unwrap();
+    }
+
+    fn random(len: usize, seed: u64) -> Vec<u64> {
+        let mut seed = seed;
+        (0..len)
+            .map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                seed >> 20
+            })
+            .collect()
+    }
+
+    fn encode_all<C: RecordCodec>(codec: &C, records: &[C::Record]) -> Vec<u8> {
+        let mut bytes = vec![0; records.len() * codec.width()];
+        for (record, buf) in records.iter().zip(bytes.chunks_mut(codec.width())) {
+            codec.encode(record, buf);
+        }
+        bytes
+    }
+
+    fn decode_all<C: RecordCodec>(codec: &C, bytes: &[u8]) -> Vec<C::Record> {
+        bytes
+            .chunks(codec.width())
+            .map(|buf| codec.decode(buf))
+            .collect()
+    }
+
+    #[test]
+    fn test_external_sort_many_spills() {
+        let dir = temp_dir("many-spills");
+        let nums = random(5_000, 7);
+        let input = encode_all(&U64Codec, &nums);
+
+        // room for 8 records, so 625 runs and more than one merge pass
+        let mut sorter = ExternalSorter::new(U64Codec, 8 * 16);
+        sorter.set_temp_dir(&dir);
+        let mut output = Vec::new();
+        let stats = sorter.sort(&input[..], &mut output).unwrap();
+
+        let mut expected = nums.clone();
+        expected.sort();
+        assert_eq!(decode_all(&U64Codec, &output), expected);
+        assert_eq!(stats.records, 5_000);
+        assert_eq!(stats.spilled_runs, 625);
+        assert_eq!(stats.merge_passes, 4);
+        assert_empty_and_remove(&dir);
+    }
+
+    #[test]
+    fn test_external_sort_keeps_existing_files() {
+        let dir = temp_dir("existing-files");
+        // plant files under the names the next runs would get
+        let next = NEXT_RUN.load(AtomicOrdering::Relaxed);
+        let planted: Vec<PathBuf> = (next..next + 100)
+            .map(|id| dir.join(format!("algo-ds-rs-{}-{}.run", process::id(), id)))
+            .collect();
+        for path in &planted {
+            fs::write(path, b"not a run").unwrap();
+        }
+
+        let nums = random(1_000, 3);
+        let input = encode_all(&U64Codec, &nums);
+        let mut sorter = ExternalSorter::new(U64Codec, 8 * 16);
+        sorter.set_temp_dir(&dir);
+        let mut output = Vec::new();
+        sorter.sort(&input[..], &mut output).unwrap();
+
+        let mut expected = nums.clone();
+        expected.sort();
+        assert_eq!(decode_all(&U64Codec, &output), expected);
+        for path in &planted {
+            assert_eq!(fs::read(path).unwrap(), b"not a run");
+            fs::remove_file(path).unwrap();
+        }
+        assert_empty_and_remove(&dir);
+    }
+
+    #[test]
+    fn test_external_sort_is_stable() {
+        let dir = temp_dir("stable");
+        let mut sorter = ExternalSorter::new(PairCodec, 100);
+        sorter.set_temp_dir(&dir);
+        let mut most_passes = 0;
+        check_stability(|records| {
+            let pairs: Vec<(u32, u32)> = records
+                .iter()
+                .map(|&(key, tag)| (key, tag as u32))
+                .collect();
+            let input = encode_all(&PairCodec, &pairs);
+            let mut output = Vec::new();
+            let stats = sorter
+                .sort_by_key(&input[..], &mut output, |&(key, _)| key)
+                .unwrap();
+            most_passes = most_passes.max(stats.merge_passes);
+
+            let sorted = decode_all(&PairCodec, &output);
+            for (record, (key, tag)) in records.iter_mut().zip(sorted) {
+                *record = (key, tag as usize);
+            }
+        })
+        .unwrap();
+        assert!(most_passes > 1);
+        assert_empty_and_remove(&dir);
+    }
+
+    #[test]
+    fn test_external_sort_with_other_sorter() {
+        let dir = temp_dir("other-sorter");
+        let codec = FixedBytes { width: 4 };
+        let words: Vec<Vec<u8>> = random(1_000, 3)
+            .into_iter()
+            .map(|x| format!("{:04}", x % 10_000).into_bytes())
+            .collect();
+        let input = encode_all(&codec, &words);
+
+        let mut sorter = ExternalSorter::with_sorter(codec, 1_000, Algorithm::Quick);
+        sorter.set_temp_dir(&dir);
+        let mut output = Vec::new();
+        let stats = sorter
+            .sort_by(&input[..], &mut output, |a, b| b.cmp(a))
+            .unwrap();
+        assert!(stats.spilled_runs > 1);
+
+        let mut expected = words.clone();
+        expected.sort_by(|a, b| b.cmp(a));
+        assert_eq!(decode_all(&codec, &output), expected);
+        assert_empty_and_remove(&dir);
+    }
+
+    #[test]
+    fn test_external_sort_fits_in_memory() {
+        let nums = random(100, 5);
+        let input = encode_all(&U64Codec, &nums);
+        let mut output = Vec::new();
+        let stats = external_sort(U64Codec, &input[..], &mut output).unwrap();
+
+        let mut expected = nums.clone();
+        expected.sort();
+        assert_eq!(decode_all(&U64Codec, &output), expected);
+        assert_eq!(
+            stats,
+            ExternalSortStats {
+                records: 100,
+                spilled_runs: 0,
+                merge_passes: 0
+            }
+        );
+
+        let mut output = Vec::new();
+        let stats = external_sort(U64Codec, io::empty(), &mut output).unwrap();
+        assert!(output.is_empty());
+        assert_eq!(stats, ExternalSortStats::default());
+    }
+
+    #[test]
+    fn test_external_sort_errors() {
+        let dir = temp_dir("errors");
+        let mut sorter = ExternalSorter::new(U64Codec, 4 * 16);
+        sorter.set_temp_dir(&dir);
+
+        // truncated last record, after a few runs were already spilled
+        let mut input = encode_all(&U64Codec, &random(20, 1));
+        input.pop();
+        let err = sorter.sort(&input[..], io::sink()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert_empty_and_remove(&dir);
+
+        let err = ExternalSorter::new(U64Codec, 31)
+            .sort(io::empty(), io::sink())
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,285 @@
+use super::insertion::insertion_sort_by;
+use super::sorter::{Algorithm, Sorter};
+use std::cmp::Ordering;
+
+// Floats sorted with the IEEE-754 total order instead of PartialOrd
+// PartialOrd can't compare NaN, so sorting floats with it leaves NaNs wherever they happen to land
+pub trait Float: Copy {
+    //IEEE-754 totalOrder, -0.0 goes before 0.0
+    fn total_cmp(&self, other: &Self) -> Ordering;
+
+    fn is_nan(self) -> bool;
+
+    fn to_f64(self) -> f64;
+}
+
+impl Float for f32 {
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        f32::total_cmp(self, other)
+    }
+
+    fn is_nan(self) -> bool {
+        f32::is_nan(self)
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl Float for f64 {
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        f64::total_cmp(self, other)
+    }
+
+    fn is_nan(self) -> bool {
+        f64::is_nan(self)
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+// Where NaNs end up, whatever their sign
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum NanPlacement {
+    First,
+    #[default]
+    Last,
+}
+
+//Comparator for any of the *_by sorts: total order, NaNs grouped on the chosen side
+pub fn total_order<T: Float>(nan: NanPlacement) -> impl Fn(&T, &T) -> Ordering + Copy {
+    move |a, b| match (a.is_nan(), b.is_nan()) {
+        (false, false) | (true, true) => a.total_cmp(b),
+        (true, false) if nan == NanPlacement::First => Ordering::Less,
+        (true, false) => Ordering::Greater,
+        (false, true) => match nan {
+            NanPlacement::First => Ordering::Greater,
+            NanPlacement::Last => Ordering::Less,
+        },
+    }
+}
+
+//Sort floats in total order with pdq_sort
+pub fn sort_floats<T: Float>(nums: &mut [T], nan: NanPlacement) {
+    sort_floats_with(&Algorithm::Pdq, nums, nan);
+}
+
+//Sort floats in total order with the chosen algorithm
+pub fn sort_floats_with<T: Float, S: Sorter>(sorter: &S, nums: &mut [T], nan: NanPlacement) {
+    sorter.sort_by(nums, total_order(nan));
+}
+
+//Sort records by a float field in total order with the chosen algorithm
+pub fn sort_floats_by_key<T, K: Float, S: Sorter, F: FnMut(&T) -> K>(
+    sorter: &S,
+    nums: &mut [T],
+    mut key: F,
+    nan: NanPlacement,
+) {
+    let order = total_order(nan);
+    sorter.sort_by(nums, |a, b| order(&key(a), &key(b)));
+}
+
+//Bucket sort for floats spread evenly over their range, O(n) on average
+//One bucket per element over [min, max] of the finite values, each bucket finishes with insertion_sort
+//Infinities and NaNs go to the end buckets, the total order puts them in place
+pub fn bucket_sort<T: Float>(nums: &mut [T], nan: NanPlacement) {
+    let len = nums.len();
+    if len < 2 {
+        return;
+    }
+
+    let order = total_order(nan);
+    let finite = nums.iter().map(|x| x.to_f64()).filter(|x| x.is_finite());
+    let (min, max) = finite.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), x| {
+        (min.min(x), max.max(x))
+    });
+    // No spread to split on, every value would land in the same bucket
+    if min >= max {
+        sort_floats(nums, nan);
+        return;
+    }
+
+    // Halved so max - min can't overflow to infinity
+    let (low, width) = (min / 2.0, max / 2.0 - min / 2.0);
+    let last = len - 1;
+    let bucket_of = |x: T| {
+        let x = x.to_f64();
+        if x.is_nan() {
+            match nan {
+                NanPlacement::First => 0,
+                NanPlacement::Last => last,
+            }
+        } else if x <= min {
+            0
+        } else if x >= max {
+            last
+        } else {
+            (((x / 2.0 - low) / width * len as f64) as usize).min(last)
+        }
+    };
+
+    let mut buckets: Vec<Vec<T>> = vec![Vec::new(); len];
+    for &x in nums.iter() {
+        buckets[bucket_of(x)].push(x);
+    }
+
+    let mut start = 0;
+    for mut bucket in buckets {
+        insertion_sort_by(&mut bucket, order);
+        nums[start..start + bucket.len()].copy_from_slice(&bucket);
+        start += bucket.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::test_utils::{check_stability, Tagged};
+
+    // Compares floats bit for bit, so NaN equals NaN and -0.0 differs from 0.0
+    fn bits(nums: &[f64]) -> Vec<u64> {
+        nums.iter().map(|x| x.to_bits()).collect()
+    }
+
+    fn messy() -> Vec<f64> {
+        vec![
+            3.5,
+            f64::NAN,
+            -0.0,
+            f64::INFINITY,
+            0.0,
+            -1.25,
+            -f64::NAN,
+            f64::NEG_INFINITY,
+            2.0,
+            -0.0,
+            f64::MIN_POSITIVE,
+        ]
+    }
+
+    #[test]
+    fn test_total_order_nan_placement() {
+        let mut last = messy();
+        sort_floats(&mut last, NanPlacement::Last);
+        let expected = [
+            f64::NEG_INFINITY,
+            -1.25,
+            -0.0,
+            -0.0,
+            0.0,
+            f64::MIN_POSITIVE,
+            2.0,
+            3.5,
+            f64::INFINITY,
+        ];
+        assert_eq!(bits(&last[..9]), bits(&expected));
+        assert!(last[9..].iter().all(|x| x.is_nan()));
+
+        let mut first = messy();
+        sort_floats(&mut first, NanPlacement::First);
+        assert!(first[..2].iter().all(|x| x.is_nan()));
+        assert_eq!(bits(&first[2..]), bits(&expected));
+    }
+
+    #[test]
+    fn test_sort_floats_with_every_algorithm() {
+        let mut expected = messy();
+        sort_floats(&mut expected, NanPlacement::First);
+
+        for algorithm in Algorithm::ALL {
+            let mut nums = messy();
+            sort_floats_with(&algorithm, &mut nums, NanPlacement::First);
+            assert_eq!(bits(&nums), bits(&expected), "{} failed", algorithm);
+        }
+    }
+
+    #[test]
+    fn test_sort_floats_by_key() {
+        let mut records = [("b", f32::NAN), ("a", 1.5), ("c", -0.0), ("d", 0.0)];
+        sort_floats_by_key(
+            &Algorithm::Merge,
+            &mut records,
+            |&(_, x)| x,
+            NanPlacement::Last,
+        );
+        let names: Vec<&str> = records.iter().map(|&(name, _)| name).collect();
+        assert_eq!(names, ["c", "d", "a", "b"]);
+    }
+
+    #[test]
+    fn test_bucket_sort_uniform() {
+        let mut seed: u64 = 13;
+        let mut nums: Vec<f64> = (0..5_000)
+            .map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                (seed >> 11) as f64 / (1u64 << 53) as f64 * 200.0 - 100.0
+            })
+            .collect();
+        let mut expected = nums.clone();
+        expected.sort_by(f64::total_cmp);
+
+        bucket_sort(&mut nums, NanPlacement::Last);
+        assert_eq!(bits(&nums), bits(&expected));
+    }
+
+    #[test]
+    fn test_bucket_sort_special_values() {
+        for nan in [NanPlacement::First, NanPlacement::Last] {
+            let mut expected = messy();
+            sort_floats(&mut expected, nan);
+            let mut nums = messy();
+            bucket_sort(&mut nums, nan);
+            assert_eq!(bits(&nums), bits(&expected));
+        }
+
+        // extreme values must not overflow the bucket width
+        let mut nums = vec![f64::MAX, 1.0, -f64::MAX, 0.0];
+        bucket_sort(&mut nums, NanPlacement::Last);
+        assert_eq!(nums, vec![-f64::MAX, 0.0, 1.0, f64::MAX]);
+
+        // no spread at all
+        let mut nums = vec![0.0, -0.0, 0.0, f32::NAN, -0.0];
+        bucket_sort(&mut nums, NanPlacement::Last);
+        let nums: Vec<u32> = nums.iter().map(|x| x.to_bits()).collect();
+        let zero = 0.0f32.to_bits();
+        let negative_zero = (-0.0f32).to_bits();
+        assert_eq!(nums[..4], [negative_zero, negative_zero, zero, zero]);
+
+        let mut empty: [f64; 0] = [];
+        bucket_sort(&mut empty, NanPlacement::Last);
+    }
+
+    // A tagged record that sorts as a float by its key alone
+    #[derive(Debug, Clone, Copy)]
+    struct TaggedFloat(Tagged);
+
+    impl Float for TaggedFloat {
+        fn total_cmp(&self, other: &Self) -> Ordering {
+            self.0 .0.cmp(&other.0 .0)
+        }
+
+        fn is_nan(self) -> bool {
+            false
+        }
+
+        fn to_f64(self) -> f64 {
+            self.0 .0 as f64
+        }
+    }
+
+    #[test]
+    fn test_bucket_sort_is_stable() {
+        check_stability(|records| {
+            let mut wrapped: Vec<TaggedFloat> = records.iter().copied().map(TaggedFloat).collect();
+            bucket_sort(&mut wrapped, NanPlacement::Last);
+            for (record, TaggedFloat(sorted)) in records.iter_mut().zip(wrapped) {
+                *record = sorted;
+            }
+        })
+        .unwrap();
+    }
+}
@@ -1,4 +1,20 @@
+use super::partial_order;
+use super::stats::{swap, Observer, Silent};
+use std::cmp::Ordering;
+
 pub fn heap_sort<T: PartialOrd>(nums: &mut [T]) {
+    heap_sort_by(nums, partial_order);
+}
+
+pub fn heap_sort_by<T, F: FnMut(&T, &T) -> Ordering>(nums: &mut [T], mut compare: F) {
+    heap_sort_observed(nums, &mut compare, &Silent);
+}
+
+pub(crate) fn heap_sort_observed<T, F, O>(nums: &mut [T], compare: &mut F, observer: &O)
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
     let len = nums.len();
     if len <= 1 {
         return;
@@ -6,27 +22,37 @@ pub fn heap_sort<T: PartialOrd>(nums: &mut [T]) {
 
     // Build a max-heap by starting from the first non-leaf node.
     for i in (0..len / 2).rev() {
-        sift_down(nums, i, len);
+        sift_down(nums, i, len, compare, observer);
     }
 
     // Repeatedly extract the maximum element from the heap and rebuild the heap.
     for end in (1..len).rev() {
-        nums.swap(0, end);
-        sift_down(nums, 0, end);
+        swap(nums, 0, end, observer);
+        sift_down(nums, 0, end, compare, observer);
     }
 }
 
-fn sift_down<T: PartialOrd>(nums: &mut [T], mut parent: usize, max: usize) {
+pub fn heap_sort_by_key<T, K: PartialOrd, F: FnMut(&T) -> K>(nums: &mut [T], mut key: F) {
+    heap_sort_by(nums, |a, b| partial_order(&key(a), &key(b)));
+}
+
+pub(crate) fn sift_down<T, F: FnMut(&T, &T) -> Ordering, O: Observer<T>>(
+    nums: &mut [T],
+    mut parent: usize,
+    max: usize,
+    compare: &mut F,
+    observer: &O,
+) {
     loop {
         let left_child = (parent << 1) + 1;
         let right_child = left_child + 1;
         let mut largest = parent;
 
-        if left_child < max && nums[left_child] > nums[largest] {
+        if left_child < max && compare(&nums[left_child], &nums[largest]) == Ordering::Greater {
             largest = left_child;
         }
 
-        if right_child < max && nums[right_child] > nums[largest] {
+        if right_child < max && compare(&nums[right_child], &nums[largest]) == Ordering::Greater {
             largest = right_child;
         }
 
@@ -34,7 +60,7 @@ fn sift_down<T: PartialOrd>(nums: &mut [T], mut parent: usize, max: usize) {
             break;
         }
 
-        nums.swap(parent, largest);
+        swap(nums, parent, largest, observer);
         parent = largest;
     }
 }
@@ -42,6 +68,7 @@ fn sift_down<T: PartialOrd>(nums: &mut [T], mut parent: usize, max: usize) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sort::test_utils::check_by_variants;
 
     #[test]
     fn test_i32_array_heap_sort() {
@@ -70,4 +97,12 @@ mod tests {
         heap_sort(&mut nums);
         assert_eq!(nums, vec![]);
     }
+
+    #[test]
+    fn test_heap_sort_by_variants() {
+        check_by_variants(
+            |nums, compare| heap_sort_by(nums, compare),
+            |records, key| heap_sort_by_key(records, key),
+        );
+    }
 }
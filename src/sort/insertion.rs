@@ -1,22 +1,55 @@
+use super::partial_order;
+use super::stats::{swap, Observer, Silent};
+use std::cmp::Ordering;
+
 pub fn insertion_sort<T: PartialOrd>(nums: &mut [T]) {
+    insertion_sort_by(nums, partial_order);
+}
+
+pub fn insertion_sort_by<T, F: FnMut(&T, &T) -> Ordering>(nums: &mut [T], mut compare: F) {
+    insertion_sort_observed(nums, &mut compare, &Silent);
+}
+
+pub(crate) fn insertion_sort_observed<T, F, O>(nums: &mut [T], compare: &mut F, observer: &O)
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
     for i in 1..nums.len() {
         let mut j = i;
-        while j > 0 && nums[j] < nums[j - 1] {
-            nums.swap(j, j - 1);
+        while j > 0 && compare(&nums[j], &nums[j - 1]) == Ordering::Less {
+            swap(nums, j, j - 1, observer);
             j -= 1;
         }
     }
 }
 
-pub fn binary_insertion_sort<T: PartialOrd + Copy>(nums: &mut [T]) {
-    for i in 1..nums.len() {
+pub fn insertion_sort_by_key<T, K: PartialOrd, F: FnMut(&T) -> K>(nums: &mut [T], mut key: F) {
+    insertion_sort_by(nums, |a, b| partial_order(&key(a), &key(b)));
+}
+
+pub fn binary_insertion_sort<T: PartialOrd>(nums: &mut [T]) {
+    binary_insertion_sort_by(nums, partial_order);
+}
+
+pub fn binary_insertion_sort_by<T, F: FnMut(&T, &T) -> Ordering>(nums: &mut [T], mut compare: F) {
+    binary_insertion_sort_from(nums, 1, &mut compare, &Silent);
+}
+
+//Insert nums[sorted..] one by one into the already sorted prefix nums[..sorted]
+pub(crate) fn binary_insertion_sort_from<T, F: FnMut(&T, &T) -> Ordering, O: Observer<T>>(
+    nums: &mut [T],
+    sorted: usize,
+    compare: &mut F,
+    observer: &O,
+) {
+    for i in sorted.max(1)..nums.len() {
         let mut left = 0;
         let mut right = i;
-        let temp = nums[i];
 
         while left < right {
             let mid = left + (right - left) / 2;
-            if temp < nums[mid] {
+            if compare(&nums[i], &nums[mid]) == Ordering::Less {
                 right = mid;
             } else {
                 left = mid + 1;
@@ -24,12 +57,23 @@ pub fn binary_insertion_sort<T: PartialOrd + Copy>(nums: &mut [T]) {
         }
 
         nums[left..=i].rotate_right(1);
+        for slot in &nums[left..=i] {
+            observer.write(slot);
+        }
     }
 }
 
+pub fn binary_insertion_sort_by_key<T, K: PartialOrd, F: FnMut(&T) -> K>(
+    nums: &mut [T],
+    mut key: F,
+) {
+    binary_insertion_sort_by(nums, |a, b| partial_order(&key(a), &key(b)));
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sort::test_utils::{check_by_variants, check_panic_safety};
 
     #[test]
     fn test_insertion_sort_empty() {
@@ -64,4 +108,35 @@ mod tests {
         binary_insertion_sort(&mut arr);
         assert_eq!(arr, ["apple", "banana", "cat", "dog", "elephant", "fox"]);
     }
+
+    #[test]
+    fn test_insertion_sort_by_variants() {
+        check_by_variants(
+            |nums, compare| insertion_sort_by(nums, compare),
+            |records, key| insertion_sort_by_key(records, key),
+        );
+    }
+
+    #[test]
+    fn test_binary_insertion_sort_by_variants() {
+        check_by_variants(
+            |nums, compare| binary_insertion_sort_by(nums, compare),
+            |records, key| binary_insertion_sort_by_key(records, key),
+        );
+    }
+
+    #[test]
+    fn test_binary_insertion_sort_heap_owning() {
+        let mut words: Vec<String> = ["pear", "apple", "fig", "kiwi", "banana"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        binary_insertion_sort(&mut words);
+        assert_eq!(words, ["apple", "banana", "fig", "kiwi", "pear"]);
+    }
+
+    #[test]
+    fn test_binary_insertion_sort_panic_safety() {
+        check_panic_safety(|nums, compare| binary_insertion_sort_by(nums, compare));
+    }
 }
@@ -0,0 +1,224 @@
+use super::heap::sift_down;
+use super::partial_order;
+use super::stats::Silent;
+use std::cmp::Ordering;
+use std::iter::FusedIterator;
+use std::mem;
+
+// K-way merge: any number of sorted iterators in, one sorted iterator out
+// The head of every source sits in a binary heap, each next pops the smallest and refills
+// from the source it came from, so merging n items from k sources costs O(n log k)
+// Equal items come out in source order, and in their own order within a source, so the merge is stable
+
+pub struct KWayMerge<I: Iterator, F> {
+    sources: Vec<I>,
+    //(head item, index of its source), the smallest on top
+    heap: Vec<(I::Item, usize)>,
+    compare: F,
+}
+
+impl<I, F> KWayMerge<I, F>
+where
+    I: Iterator,
+    F: FnMut(&I::Item, &I::Item) -> Ordering,
+{
+    fn new(sources: Vec<I>, compare: F) -> Self {
+        let mut merge = KWayMerge {
+            heap: Vec::with_capacity(sources.len()),
+            sources,
+            compare,
+        };
+        for (source, iter) in merge.sources.iter_mut().enumerate() {
+            if let Some(item) = iter.next() {
+                merge.heap.push((item, source));
+            }
+        }
+        let len = merge.heap.len();
+        for i in (0..len / 2).rev() {
+            merge.sift_down(i);
+        }
+        merge
+    }
+
+    fn sift_down(&mut self, i: usize) {
+        // sift_down keeps a max-heap, flipping the order puts the smallest item on top,
+        // the earlier source wins a tie
+        let compare = &mut self.compare;
+        let mut order =
+            |a: &(I::Item, usize), b: &(I::Item, usize)| compare(&b.0, &a.0).then(b.1.cmp(&a.1));
+        let len = self.heap.len();
+        sift_down(&mut self.heap, i, len, &mut order, &Silent);
+    }
+}
+
+impl<I, F> Iterator for KWayMerge<I, F>
+where
+    I: Iterator,
+    F: FnMut(&I::Item, &I::Item) -> Ordering,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let source = self.heap.first()?.1;
+        // Refill the top from the same source, or drop it once that source is empty
+        let item = match self.sources[source].next() {
+            Some(next) => mem::replace(&mut self.heap[0].0, next),
+            None => self.heap.swap_remove(0).0,
+        };
+        self.sift_down(0);
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let pending = self.heap.len();
+        self.sources
+            .iter()
+            .fold((pending, Some(pending)), |(low, high), source| {
+                let (source_low, source_high) = source.size_hint();
+                (
+                    low.saturating_add(source_low),
+                    high.zip(source_high).and_then(|(a, b)| a.checked_add(b)),
+                )
+            })
+    }
+}
+
+impl<I, F> FusedIterator for KWayMerge<I, F>
+where
+    I: Iterator,
+    F: FnMut(&I::Item, &I::Item) -> Ordering,
+{
+}
+
+// The iterator of each source and what it yields, when the sources come as an S
+type Source<S> = <<S as IntoIterator>::Item as IntoIterator>::IntoIter;
+type SourceItem<S> = <<S as IntoIterator>::Item as IntoIterator>::Item;
+
+//Merge sources that are each sorted ascending into one ascending iterator
+pub fn kway_merge<S>(
+    sources: S,
+) -> KWayMerge<Source<S>, impl FnMut(&SourceItem<S>, &SourceItem<S>) -> Ordering>
+where
+    S: IntoIterator,
+    S::Item: IntoIterator,
+    SourceItem<S>: PartialOrd,
+{
+    kway_merge_by(sources, partial_order)
+}
+
+//Every source has to be sorted by compare already, nothing checks it
+pub fn kway_merge_by<S, F>(sources: S, compare: F) -> KWayMerge<Source<S>, F>
+where
+    S: IntoIterator,
+    S::Item: IntoIterator,
+    F: FnMut(&SourceItem<S>, &SourceItem<S>) -> Ordering,
+{
+    KWayMerge::new(
+        sources.into_iter().map(IntoIterator::into_iter).collect(),
+        compare,
+    )
+}
+
+pub fn kway_merge_by_key<S, K, F>(
+    sources: S,
+    mut key: F,
+) -> KWayMerge<Source<S>, impl FnMut(&SourceItem<S>, &SourceItem<S>) -> Ordering>
+where
+    S: IntoIterator,
+    S::Item: IntoIterator,
+    K: PartialOrd,
+    F: FnMut(&SourceItem<S>) -> K,
+{
+    kway_merge_by(sources, move |a, b| partial_order(&key(a), &key(b)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::merge::merge_sort_by_key;
+    use crate::sort::test_utils::{check_stability, Tagged};
+    use std::cell::Cell;
+
+    #[test]
+    fn test_kway_merge() {
+        let sources = vec![
+            vec![1, 4, 7, 10],
+            vec![2, 5, 8],
+            vec![],
+            vec![0, 3, 6, 9, 11],
+        ];
+        let merged = kway_merge(sources);
+        assert_eq!(merged.size_hint(), (12, Some(12)));
+        assert_eq!(merged.collect::<Vec<_>>(), (0..12).collect::<Vec<_>>());
+
+        assert_eq!(kway_merge(Vec::<Vec<i32>>::new()).next(), None);
+        assert_eq!(kway_merge([vec![3, 1, 2]]).collect::<Vec<_>>(), [3, 1, 2]);
+    }
+
+    #[test]
+    fn test_kway_merge_many_sources() {
+        let mut seed = 17u64;
+        let sources: Vec<Vec<u32>> = (0..100)
+            .map(|len| {
+                let mut run: Vec<u32> = (0..len)
+                    .map(|_| {
+                        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                        (seed >> 40) as u32 % 1000
+                    })
+                    .collect();
+                run.sort();
+                run
+            })
+            .collect();
+        let mut expected: Vec<u32> = sources.concat();
+        expected.sort();
+        assert_eq!(kway_merge(sources).collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_kway_merge_is_stable() {
+        // Consecutive chunks sorted on their own, equal keys must come out in source then chunk order
+        for sources in [1, 2, 5, 16] {
+            check_stability(|records| {
+                let chunk = records.len() / sources + 1;
+                let mut chunks: Vec<Vec<Tagged>> =
+                    records.chunks(chunk).map(<[_]>::to_vec).collect();
+                for run in &mut chunks {
+                    merge_sort_by_key(run, |&(key, _)| key);
+                }
+                for (record, merged) in records
+                    .iter_mut()
+                    .zip(kway_merge_by_key(chunks, |&(key, _)| key))
+                {
+                    *record = merged;
+                }
+            })
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_kway_merge_is_lazy() {
+        let pulled = Cell::new(0);
+        let sources = (0..3).map(|source| {
+            let pulled = &pulled;
+            (0..).map(move |i| {
+                pulled.set(pulled.get() + 1);
+                i * 3 + source
+            })
+        });
+        let first: Vec<u64> = kway_merge(sources).take(10).collect();
+        assert_eq!(first, (0..10).collect::<Vec<_>>());
+        // one head per source plus one refill per item taken
+        assert_eq!(pulled.get(), 3 + 10);
+    }
+
+    #[test]
+    fn test_kway_merge_by_descending() {
+        let merged: Vec<_> = kway_merge_by([vec!["pear", "fig"], vec!["kiwi", "apple"]], |a, b| {
+            b.cmp(a)
+        })
+        .collect();
+        assert_eq!(merged, ["pear", "kiwi", "fig", "apple"]);
+    }
+}
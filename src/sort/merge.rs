@@ -1,57 +1,114 @@
-pub fn merge_sort<T: PartialOrd + Copy>(nums: &mut [T]) {
+use super::partial_order;
+use super::stats::{Observer, Silent};
+use std::cmp::Ordering;
+use std::ptr;
+
+pub fn merge_sort<T: PartialOrd>(nums: &mut [T]) {
+    merge_sort_by(nums, partial_order);
+}
+
+pub fn merge_sort_by<T, F: FnMut(&T, &T) -> Ordering>(nums: &mut [T], mut compare: F) {
+    merge_sort_observed(nums, &mut compare, &Silent);
+}
+
+pub(crate) fn merge_sort_observed<T, F, O>(nums: &mut [T], compare: &mut F, observer: &O)
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    if nums.len() <= 1 {
+        return;
+    }
+    // one scratch buffer for every merge, it never holds more than nums
+    let mut scratch: Vec<T> = Vec::with_capacity(nums.len());
+    observer.alloc(nums.len());
+    sort(nums, &mut scratch, compare, observer);
+}
+
+pub fn merge_sort_by_key<T, K: PartialOrd, F: FnMut(&T) -> K>(nums: &mut [T], mut key: F) {
+    merge_sort_by(nums, |a, b| partial_order(&key(a), &key(b)));
+}
+
+pub(crate) fn sort<T, F: FnMut(&T, &T) -> Ordering, O: Observer<T>>(
+    nums: &mut [T],
+    scratch: &mut Vec<T>,
+    compare: &mut F,
+    observer: &O,
+) {
     if nums.len() > 1 {
+        observer.enter();
         let mid = nums.len() >> 1;
         // sort the first half
-        merge_sort(&mut nums[..mid]);
+        sort(&mut nums[..mid], scratch, compare, observer);
         // sort the last half
-        merge_sort(&mut nums[mid..]);
+        sort(&mut nums[mid..], scratch, compare, observer);
         // merge all
-        merge(nums, mid);
+        merge(nums, mid, scratch, compare, observer);
+        observer.leave();
     }
 }
 
-fn merge<T: PartialOrd + Copy>(nums: &mut [T], mid: usize) {
+// Elements are moved bitwise into scratch and back, so T does not need to be Copy
+// scratch keeps a len of 0, so if compare panics nothing is dropped twice and nums still owns everything
+pub(crate) fn merge<T, F: FnMut(&T, &T) -> Ordering, O: Observer<T>>(
+    nums: &mut [T],
+    mid: usize,
+    scratch: &mut Vec<T>,
+    compare: &mut F,
+    observer: &O,
+) {
+    let len = nums.len();
+    debug_assert!(scratch.is_empty() && scratch.capacity() >= len);
     // mark element in first half of data
     let mut i = 0;
     // mark element in last half of data
     let mut k = mid;
-    let mut temp = Vec::new();
-
-    for _j in 0..nums.len() {
-        if k == nums.len() || i == mid {
-            break;
-        }
+    // mark the next free slot in scratch
+    let mut j = 0;
+    let temp = scratch.as_mut_ptr();
 
-        // put into a temp collection
-        if nums[i] < nums[k] {
-            temp.push(nums[i]);
+    while i < mid && k < len {
+        // put into a temp collection, ties take the left element first so the sort is stable
+        let next = if compare(&nums[i], &nums[k]) != Ordering::Greater {
             i += 1;
+            i - 1
         } else {
-            temp.push(nums[k]);
             k += 1;
+            k - 1
+        };
+        // SAFETY: next < len and j < len, scratch has room for len elements
+        unsafe {
+            ptr::copy_nonoverlapping(nums.as_ptr().add(next), temp.add(j), 1);
+            observer.write(&*temp.add(j));
         }
+        j += 1;
     }
 
-    // to make sure all data been solved
-    if i < mid && k == nums.len() {
-        for j in i..mid {
-            temp.push(nums[j]);
+    // SAFETY: every element of nums is copied exactly once into scratch[..len],
+    // then the whole run is copied back at once. nums is untouched until then,
+    // so a panic in compare or observer leaves it as it was
+    unsafe {
+        // to make sure all data been solved
+        let rest = j;
+        ptr::copy_nonoverlapping(nums.as_ptr().add(i), temp.add(j), mid - i);
+        j += mid - i;
+        ptr::copy_nonoverlapping(nums.as_ptr().add(k), temp.add(j), len - k);
+        for slot in rest..len {
+            observer.write(&*temp.add(slot));
         }
-    } else if i == mid && k < nums.len() {
-        for j in k..nums.len() {
-            temp.push(nums[j]);
-        }
-    }
 
-    // put temp data back to nums, finish sort
-    for j in 0..nums.len() {
-        nums[j] = temp[j];
+        // put temp data back to nums, finish sort
+        ptr::copy_nonoverlapping(temp, nums.as_mut_ptr(), len);
+    }
+    for slot in nums.iter() {
+        observer.write(slot);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sort::test_utils::{check_by_variants, check_panic_safety, check_stability};
 
     #[test]
     fn test_merge_sort_vec_i32() {
@@ -87,4 +144,41 @@ mod tests {
         merge_sort(&mut nums);
         assert_eq!(nums, Vec::<i32>::new());
     }
+
+    #[test]
+    fn test_merge_sort_by_variants() {
+        check_by_variants(
+            |nums, compare| merge_sort_by(nums, compare),
+            |records, key| merge_sort_by_key(records, key),
+        );
+    }
+
+    #[test]
+    fn test_merge_sort_heap_owning() {
+        let mut words: Vec<String> = ["pear", "apple", "fig", "kiwi", "banana"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        merge_sort(&mut words);
+        assert_eq!(words, ["apple", "banana", "fig", "kiwi", "pear"]);
+
+        let mut vecs = vec![vec![3, 1], vec![], vec![2], vec![1, 5, 9]];
+        merge_sort_by_key(&mut vecs, |v| v.len());
+        assert_eq!(vecs, vec![vec![], vec![2], vec![3, 1], vec![1, 5, 9]]);
+    }
+
+    #[test]
+    fn test_merge_sort_panic_safety() {
+        check_panic_safety(|nums, compare| merge_sort_by(nums, compare));
+    }
+
+    #[test]
+    fn test_merge_sort_is_stable() {
+        check_stability(|records| merge_sort_by_key(records, |&(key, _)| key)).unwrap();
+
+        // equal keys at the boundary of the two halves, the left one has to come out first
+        let mut records = [(1, 'a'), (2, 'b'), (1, 'c'), (2, 'd')];
+        merge_sort_by_key(&mut records, |&(key, _)| key);
+        assert_eq!(records, [(1, 'a'), (1, 'c'), (2, 'b'), (2, 'd')]);
+    }
 }
@@ -1,7 +1,29 @@
+use std::cmp::Ordering;
+
 pub mod bubble;
+pub mod external;
+pub mod float;
 pub mod heap;
 pub mod insertion;
+pub mod kway;
 pub mod merge;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod pdq;
 pub mod quick;
+pub mod radix;
+pub mod select;
 pub mod selection;
 pub mod shell;
+pub mod sorter;
+pub mod stats;
+pub mod tim;
+pub mod trace;
+
+#[cfg(test)]
+mod test_utils;
+
+//Order used by the PartialOrd sorts, incomparable values like NaN count as equal
+pub(crate) fn partial_order<T: PartialOrd>(a: &T, b: &T) -> Ordering {
+    a.partial_cmp(b).unwrap_or(Ordering::Equal)
+}
@@ -0,0 +1,237 @@
+use super::partial_order;
+use super::stats::Silent;
+use super::{merge, quick};
+use std::cmp::Ordering;
+use std::thread;
+
+// Parallel versions of merge_sort and quick_sort on scoped std threads
+// Both split the slice exactly like the serial sort, so the result is identical whatever the thread count
+// Only the independent halves run on other threads, everything under the cutoff runs the serial code
+// par_merge_sort is stable like merge_sort, par_quick_sort is not
+
+// Slices this short are not worth a thread
+const SEQUENTIAL_CUTOFF: usize = 1 << 13;
+
+pub fn par_merge_sort<T: PartialOrd + Send>(nums: &mut [T]) {
+    par_merge_sort_by(nums, partial_order);
+}
+
+pub fn par_merge_sort_by<T, F>(nums: &mut [T], compare: F)
+where
+    T: Send,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    merge_sort_with_threads(nums, &compare, threads());
+}
+
+pub fn par_merge_sort_by_key<T, K, F>(nums: &mut [T], key: F)
+where
+    T: Send,
+    K: PartialOrd,
+    F: Fn(&T) -> K + Sync,
+{
+    par_merge_sort_by(nums, |a, b| partial_order(&key(a), &key(b)));
+}
+
+pub fn par_quick_sort<T: PartialOrd + Send>(nums: &mut [T]) {
+    par_quick_sort_by(nums, partial_order);
+}
+
+pub fn par_quick_sort_by<T, F>(nums: &mut [T], compare: F)
+where
+    T: Send,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    quick_sort_with_threads(nums, &compare, threads());
+}
+
+pub fn par_quick_sort_by_key<T, K, F>(nums: &mut [T], key: F)
+where
+    T: Send,
+    K: PartialOrd,
+    F: Fn(&T) -> K + Sync,
+{
+    par_quick_sort_by(nums, |a, b| partial_order(&key(a), &key(b)));
+}
+
+fn threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+fn merge_sort_with_threads<T, F>(nums: &mut [T], compare: &F, threads: usize)
+where
+    T: Send,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    if nums.len() <= 1 {
+        return;
+    }
+    let mut scratch: Vec<T> = Vec::with_capacity(nums.len());
+    par_merge(nums, &mut scratch, compare, threads);
+}
+
+//Same recursion as merge::sort, the two halves are sorted on separate threads while there are threads left
+fn par_merge<T, F>(nums: &mut [T], scratch: &mut Vec<T>, compare: &F, threads: usize)
+where
+    T: Send,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    let mut compare_mut = |a: &T, b: &T| compare(a, b);
+    if threads <= 1 || nums.len() <= SEQUENTIAL_CUTOFF {
+        merge::sort(nums, scratch, &mut compare_mut, &Silent);
+        return;
+    }
+
+    let mid = nums.len() >> 1;
+    let (left, right) = nums.split_at_mut(mid);
+    thread::scope(|s| {
+        s.spawn(|| {
+            // each thread needs its own scratch, the shared one is only big enough for one merge
+            let mut left_scratch = Vec::with_capacity(left.len());
+            par_merge(left, &mut left_scratch, compare, threads / 2);
+        });
+        par_merge(right, scratch, compare, threads - threads / 2);
+    });
+    merge::merge(nums, mid, scratch, &mut compare_mut, &Silent);
+}
+
+fn quick_sort_with_threads<T, F>(nums: &mut [T], compare: &F, threads: usize)
+where
+    T: Send,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    // same depth limit as quick_sort_by
+    let limit = 2 * (usize::BITS - nums.len().leading_zeros());
+    par_quick(nums, limit, compare, threads);
+}
+
+//Same steps as quick::sort: partition here, then hand both sides to separate threads with the same limit
+fn par_quick<T, F>(nums: &mut [T], limit: u32, compare: &F, threads: usize)
+where
+    T: Send,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    let mut compare_mut = |a: &T, b: &T| compare(a, b);
+    // limit 0 is left to quick::sort so the heap_sort fallback is the same
+    if threads <= 1 || nums.len() <= SEQUENTIAL_CUTOFF || limit == 0 {
+        quick::sort(nums, limit, &mut compare_mut, &Silent);
+        return;
+    }
+
+    let pivot = quick::choose_pivot(nums, &mut compare_mut);
+    let (lt, gt) = quick::partition(nums, pivot, &mut compare_mut, &Silent);
+    let (left, rest) = nums.split_at_mut(lt);
+    let right = &mut rest[gt - lt..];
+    thread::scope(|s| {
+        s.spawn(|| par_quick(left, limit - 1, compare, threads / 2));
+        par_quick(right, limit - 1, compare, threads - threads / 2);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::merge::merge_sort_by;
+    use crate::sort::quick::quick_sort_by;
+    use crate::sort::test_utils::{check_stability, check_stability_on, key_shapes};
+
+    // Records with many equal keys, the id tells apart how equal keys ended up ordered
+    fn records(len: usize, seed: u64) -> Vec<(u32, usize)> {
+        let mut seed = seed;
+        (0..len)
+            .map(|id| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                ((seed >> 33) as u32 % 1000, id)
+            })
+            .collect()
+    }
+
+    fn by_key(a: &(u32, usize), b: &(u32, usize)) -> Ordering {
+        a.0.cmp(&b.0)
+    }
+
+    #[test]
+    fn test_par_merge_sort_matches_serial() {
+        for len in [0, 1, 100, SEQUENTIAL_CUTOFF + 1, 100_000] {
+            let mut expected = records(len, len as u64);
+            merge_sort_by(&mut expected, by_key);
+            // merge_sort is stable, so the ids of equal keys stay ascending
+            assert!(expected.windows(2).all(|pair| pair[0] < pair[1]));
+
+            for threads in [1, 2, 3, 8] {
+                let mut nums = records(len, len as u64);
+                merge_sort_with_threads(&mut nums, &by_key, threads);
+                assert_eq!(nums, expected, "len {} threads {}", len, threads);
+            }
+        }
+    }
+
+    #[test]
+    fn test_par_merge_sort_is_stable() {
+        check_stability(|records| par_merge_sort_by_key(records, |&(key, _)| key)).unwrap();
+
+        // long enough to be split across threads
+        for threads in [2, 3, 8] {
+            let inputs = key_shapes(3 * SEQUENTIAL_CUTOFF);
+            check_stability_on(inputs, |records| {
+                merge_sort_with_threads(records, &by_key, threads)
+            })
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_par_quick_sort_matches_serial() {
+        for len in [0, 1, 100, SEQUENTIAL_CUTOFF + 1, 100_000] {
+            let mut expected = records(len, len as u64);
+            quick_sort_by(&mut expected, by_key);
+
+            for threads in [1, 2, 3, 8] {
+                let mut nums = records(len, len as u64);
+                quick_sort_with_threads(&mut nums, &by_key, threads);
+                assert_eq!(nums, expected, "len {} threads {}", len, threads);
+            }
+        }
+    }
+
+    #[test]
+    fn test_par_sorts_public_api() {
+        let mut nums: Vec<i64> = (0..50_000).map(|x| (x * 7919) % 50_000 - 25_000).collect();
+        let mut expected = nums.clone();
+        expected.sort();
+
+        let mut merged = nums.clone();
+        par_merge_sort(&mut merged);
+        assert_eq!(merged, expected);
+
+        par_quick_sort(&mut nums);
+        assert_eq!(nums, expected);
+
+        let mut words: Vec<String> = (0..20_000).map(|x| format!("w{}", x % 977)).collect();
+        let mut expected = words.clone();
+        expected.sort_by_key(|w| std::cmp::Reverse(w.len()));
+        par_merge_sort_by_key(&mut words, |w| std::cmp::Reverse(w.len()));
+        assert!(words.windows(2).all(|w| w[0].len() >= w[1].len()));
+
+        par_quick_sort_by(&mut words, |a, b| b.cmp(a));
+        expected.sort_by(|a, b| b.cmp(a));
+        assert_eq!(words, expected);
+    }
+
+    #[test]
+    fn test_par_quick_sort_adversarial_depth() {
+        // sorted, reversed and all-equal inputs that hit the depth limit on bad pivots
+        let len = 3 * SEQUENTIAL_CUTOFF;
+        let inputs: [Vec<u32>; 3] = [
+            (0..len as u32).collect(),
+            (0..len as u32).rev().collect(),
+            vec![7; len],
+        ];
+        for mut nums in inputs {
+            let mut expected = nums.clone();
+            expected.sort();
+            quick_sort_with_threads(&mut nums, &u32::cmp, 4);
+            assert_eq!(nums, expected);
+        }
+    }
+}
@@ -0,0 +1,519 @@
+use super::heap::heap_sort_observed;
+use super::insertion::insertion_sort_observed;
+use super::partial_order;
+use super::stats::{reverse, swap, Observer, Silent};
+use std::cmp::Ordering;
+
+// Pattern-defeating quicksort (Orson Peters), the same design as std's sort_unstable:
+// - sorted and reverse sorted inputs are detected while choosing the pivot and finish in O(n)
+// - partitioning works on blocks of offsets so the hot loop has no unpredictable branches
+// - unbalanced partitions shuffle a few elements to break the pattern that caused them
+// - too many unbalanced partitions fall back to heap_sort, short slices to insertion_sort
+pub fn pdq_sort<T: PartialOrd>(nums: &mut [T]) {
+    pdq_sort_by(nums, partial_order);
+}
+
+pub fn pdq_sort_by<T, F: FnMut(&T, &T) -> Ordering>(nums: &mut [T], mut compare: F) {
+    pdq_sort_observed(nums, &mut compare, &Silent);
+}
+
+pub(crate) fn pdq_sort_observed<T, F, O>(nums: &mut [T], compare: &mut F, observer: &O)
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    let limit = usize::BITS - nums.len().leading_zeros();
+    recurse(nums, compare, None, limit, observer);
+}
+
+pub fn pdq_sort_by_key<T, K: PartialOrd, F: FnMut(&T) -> K>(nums: &mut [T], mut key: F) {
+    pdq_sort_by(nums, |a, b| partial_order(&key(a), &key(b)));
+}
+
+// Slices this short are finished with insertion sort
+const MAX_INSERTION: usize = 20;
+// Slices this long take the pivot from medians of three
+const SHORTEST_MEDIAN_OF_MEDIANS: usize = 50;
+// Number of offsets scanned at once on each side of the block partition
+const BLOCK: usize = 128;
+
+fn is_less<T, F: FnMut(&T, &T) -> Ordering>(a: &T, b: &T, compare: &mut F) -> bool {
+    compare(a, b) == Ordering::Less
+}
+
+// pred is the pivot of the parent partition, every element of nums is >= pred
+fn recurse<'a, T, F: FnMut(&T, &T) -> Ordering, O: Observer<T>>(
+    mut nums: &'a mut [T],
+    compare: &mut F,
+    mut pred: Option<&'a T>,
+    mut limit: u32,
+    observer: &O,
+) {
+    let mut was_balanced = true;
+    let mut was_partitioned = true;
+
+    observer.enter();
+    loop {
+        let len = nums.len();
+        if len <= MAX_INSERTION {
+            insertion_sort_observed(nums, compare, observer);
+            break;
+        }
+
+        // Too many bad partitions, give up on quicksort for this slice
+        if limit == 0 {
+            heap_sort_observed(nums, compare, observer);
+            break;
+        }
+
+        if !was_balanced {
+            break_patterns(nums, observer);
+            limit -= 1;
+        }
+
+        let (pivot, likely_sorted) = choose_pivot(nums, compare, observer);
+
+        // The last partition was clean and the pivot looks sorted, try to finish with a few shifts
+        if was_balanced
+            && was_partitioned
+            && likely_sorted
+            && partial_insertion_sort(nums, compare, observer)
+        {
+            break;
+        }
+
+        // The pivot equals the parent pivot, so it is the smallest value left:
+        // put every copy of it on the left and only keep sorting the rest
+        if let Some(pred) = pred {
+            if !is_less(pred, &nums[pivot], compare) {
+                let mid = partition_equal(nums, pivot, compare, observer);
+                nums = &mut nums[mid..];
+                continue;
+            }
+        }
+
+        let (mid, partitioned) = partition(nums, pivot, compare, observer);
+        was_balanced = mid.min(len - mid) >= len / 8;
+        was_partitioned = partitioned;
+
+        // Recurse into the smaller side and loop on the larger one
+        let (left, rest) = nums.split_at_mut(mid);
+        let (pivot, right) = rest.split_at_mut(1);
+        let pivot = &pivot[0];
+        if left.len() < right.len() {
+            recurse(left, compare, pred, limit, observer);
+            nums = right;
+            pred = Some(pivot);
+        } else {
+            recurse(right, compare, Some(pivot), limit, observer);
+            nums = left;
+        }
+    }
+    observer.leave();
+}
+
+//Returns the pivot index and whether the slice already looked sorted
+//Counts swaps while sorting the samples, a reversed looking slice gets reversed first
+fn choose_pivot<T, F: FnMut(&T, &T) -> Ordering, O: Observer<T>>(
+    nums: &mut [T],
+    compare: &mut F,
+    observer: &O,
+) -> (usize, bool) {
+    const MAX_SWAPS: usize = 4 * 3;
+    let len = nums.len();
+
+    let mut a = len / 4;
+    let mut b = len / 4 * 2;
+    let mut c = len / 4 * 3;
+    let mut swaps = 0;
+
+    if len >= 8 {
+        let mut sort2 = |a: &mut usize, b: &mut usize, compare: &mut F| {
+            if is_less(&nums[*b], &nums[*a], compare) {
+                std::mem::swap(a, b);
+                swaps += 1;
+            }
+        };
+        let mut sort3 = |a: &mut usize, b: &mut usize, c: &mut usize, compare: &mut F| {
+            sort2(a, b, compare);
+            sort2(b, c, compare);
+            sort2(a, b, compare);
+        };
+
+        if len >= SHORTEST_MEDIAN_OF_MEDIANS {
+            // Replace each sample with the median of its neighbourhood
+            for x in [&mut a, &mut b, &mut c] {
+                let (mut low, mut high) = (*x - 1, *x + 1);
+                sort3(&mut low, x, &mut high, compare);
+            }
+        }
+        sort3(&mut a, &mut b, &mut c, compare);
+    }
+
+    if swaps < MAX_SWAPS {
+        (b, swaps == 0)
+    } else {
+        // Every sample pair was out of order, the slice is probably descending
+        reverse(nums, observer);
+        (len - 1 - b, true)
+    }
+}
+
+//Sort a nearly sorted slice by shifting a few out of place elements
+//Gives up and returns false after too many of them
+fn partial_insertion_sort<T, F: FnMut(&T, &T) -> Ordering, O: Observer<T>>(
+    nums: &mut [T],
+    compare: &mut F,
+    observer: &O,
+) -> bool {
+    const MAX_STEPS: usize = 5;
+    const SHORTEST_SHIFTING: usize = 50;
+    let len = nums.len();
+    let mut i = 1;
+
+    for _ in 0..MAX_STEPS {
+        // Find the next adjacent out of order pair
+        while i < len && !is_less(&nums[i], &nums[i - 1], compare) {
+            i += 1;
+        }
+
+        if i == len {
+            return true;
+        }
+
+        // Shifting on short slices costs more than it saves
+        if len < SHORTEST_SHIFTING {
+            return false;
+        }
+
+        swap(nums, i - 1, i, observer);
+        shift_tail(&mut nums[..i], compare, observer);
+        shift_head(&mut nums[i..], compare, observer);
+    }
+
+    false
+}
+
+//Move the last element left until it is in order
+fn shift_tail<T, F, O>(nums: &mut [T], compare: &mut F, observer: &O)
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    let mut i = nums.len();
+    while i > 1 && is_less(&nums[i - 1], &nums[i - 2], compare) {
+        swap(nums, i - 1, i - 2, observer);
+        i -= 1;
+    }
+}
+
+//Move the first element right until it is in order
+fn shift_head<T, F, O>(nums: &mut [T], compare: &mut F, observer: &O)
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    let mut i = 0;
+    while i + 1 < nums.len() && is_less(&nums[i + 1], &nums[i], compare) {
+        swap(nums, i, i + 1, observer);
+        i += 1;
+    }
+}
+
+//Swap three elements around the middle with pseudo random positions
+fn break_patterns<T, O: Observer<T>>(nums: &mut [T], observer: &O) {
+    let len = nums.len();
+    // xorshift seeded with the length, deterministic between runs
+    let mut random = len as u32;
+    let mut next = || {
+        random ^= random << 13;
+        random ^= random >> 17;
+        random ^= random << 5;
+        random as usize
+    };
+
+    let modulus = len.next_power_of_two();
+    let pos = len / 4 * 2;
+    for i in 0..3 {
+        let mut other = next() & (modulus - 1);
+        if other >= len {
+            other -= len;
+        }
+        swap(nums, pos - 1 + i, other, observer);
+    }
+}
+
+// Put every element equal to the pivot at the front, nums must hold nothing smaller than it
+// Returns how many elements (pivot included) were moved there
+fn partition_equal<T, F: FnMut(&T, &T) -> Ordering, O: Observer<T>>(
+    nums: &mut [T],
+    pivot_index: usize,
+    compare: &mut F,
+    observer: &O,
+) -> usize {
+    swap(nums, 0, pivot_index, observer);
+    let (pivot, nums) = nums.split_at_mut(1);
+    let pivot = &pivot[0];
+
+    let mut l = 0;
+    let mut r = nums.len();
+    loop {
+        while l < r && !is_less(pivot, &nums[l], compare) {
+            l += 1;
+        }
+        while l < r && is_less(pivot, &nums[r - 1], compare) {
+            r -= 1;
+        }
+        if l >= r {
+            break;
+        }
+        r -= 1;
+        swap(nums, l, r, observer);
+        l += 1;
+    }
+
+    l + 1
+}
+
+// Partition around nums[pivot_index], elements smaller than the pivot go left
+// Returns the final pivot position and whether the slice was already partitioned
+fn partition<T, F: FnMut(&T, &T) -> Ordering, O: Observer<T>>(
+    nums: &mut [T],
+    pivot_index: usize,
+    compare: &mut F,
+    observer: &O,
+) -> (usize, bool) {
+    swap(nums, 0, pivot_index, observer);
+    let (pivot_slot, rest) = nums.split_at_mut(1);
+    let pivot = &pivot_slot[0];
+
+    // Skip the prefix and suffix that are already in place
+    let mut l = 0;
+    let mut r = rest.len();
+    while l < r && is_less(&rest[l], pivot, compare) {
+        l += 1;
+    }
+    while l < r && !is_less(&rest[r - 1], pivot, compare) {
+        r -= 1;
+    }
+
+    let mid = l + partition_in_blocks(&mut rest[l..r], pivot, compare, observer);
+    swap(nums, 0, mid, observer); // Move the pivot element to its final position
+    (mid, l >= r)
+}
+
+// BlockQuicksort: scan a block on each side, record the offsets of misplaced elements,
+// then swap them in pairs. The scans only add comparison results, they never branch on them
+// Returns the number of elements smaller than the pivot
+fn partition_in_blocks<T, F: FnMut(&T, &T) -> Ordering, O: Observer<T>>(
+    nums: &mut [T],
+    pivot: &T,
+    compare: &mut F,
+    observer: &O,
+) -> usize {
+    // nums[..l] is smaller than the pivot, nums[r..] is not
+    let mut l = 0;
+    let mut r = nums.len();
+
+    // Offsets of misplaced elements in the current left block, pending ones are start..end
+    let mut offsets_l = [0u8; BLOCK];
+    let mut start_l = 0;
+    let mut end_l = 0;
+    let mut block_l = BLOCK;
+
+    // Offsets counted from the end of the current right block
+    let mut offsets_r = [0u8; BLOCK];
+    let mut start_r = 0;
+    let mut end_r = 0;
+    let mut block_r = BLOCK;
+
+    loop {
+        let width = r - l;
+        let is_done = width <= 2 * BLOCK;
+
+        if is_done {
+            // Shrink the blocks so together they cover exactly what is left
+            let mut remaining = width;
+            if start_l < end_l || start_r < end_r {
+                remaining -= BLOCK;
+            }
+            if start_l < end_l {
+                block_r = remaining;
+            } else if start_r < end_r {
+                block_l = remaining;
+            } else {
+                block_l = remaining / 2;
+                block_r = remaining - block_l;
+            }
+        }
+
+        if start_l == end_l {
+            start_l = 0;
+            end_l = 0;
+            for i in 0..block_l {
+                offsets_l[end_l] = i as u8;
+                end_l += !is_less(&nums[l + i], pivot, compare) as usize;
+            }
+        }
+
+        if start_r == end_r {
+            start_r = 0;
+            end_r = 0;
+            for i in 0..block_r {
+                offsets_r[end_r] = i as u8;
+                end_r += is_less(&nums[r - 1 - i], pivot, compare) as usize;
+            }
+        }
+
+        let count = (end_l - start_l).min(end_r - start_r);
+        for _ in 0..count {
+            swap(
+                nums,
+                l + offsets_l[start_l] as usize,
+                r - 1 - offsets_r[start_r] as usize,
+                observer,
+            );
+            start_l += 1;
+            start_r += 1;
+        }
+
+        if start_l == end_l {
+            l += block_l;
+        }
+        if start_r == end_r {
+            r -= block_r;
+        }
+
+        if is_done {
+            break;
+        }
+    }
+
+    // At most one block still has misplaced elements, move them to its far side
+    if start_l < end_l {
+        while start_l < end_l {
+            end_l -= 1;
+            swap(nums, l + offsets_l[end_l] as usize, r - 1, observer);
+            r -= 1;
+        }
+        r
+    } else if start_r < end_r {
+        while start_r < end_r {
+            end_r -= 1;
+            swap(nums, l, r - 1 - offsets_r[end_r] as usize, observer);
+            l += 1;
+        }
+        l
+    } else {
+        l
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::test_utils::check_panic_safety;
+
+    fn pseudo_random(len: usize, modulo: u64) -> Vec<u64> {
+        let mut seed: u64 = 11;
+        (0..len)
+            .map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                (seed >> 33) % modulo
+            })
+            .collect()
+    }
+
+    // Sorts a copy of nums, checks the result and returns the number of comparisons
+    fn count_comparisons(nums: &[u64]) -> usize {
+        let mut sorted = nums.to_vec();
+        let mut comparisons = 0;
+        pdq_sort_by(&mut sorted, |a, b| {
+            comparisons += 1;
+            a.cmp(b)
+        });
+
+        let mut expected = nums.to_vec();
+        expected.sort_unstable();
+        assert_eq!(sorted, expected);
+        comparisons
+    }
+
+    #[test]
+    fn test_pdq_sort_small() {
+        let mut empty: Vec<i32> = Vec::new();
+        pdq_sort(&mut empty);
+        assert_eq!(empty, vec![]);
+
+        let mut nums = [5, 2, 1, 3, 6, 4];
+        pdq_sort(&mut nums);
+        assert_eq!(nums, [1, 2, 3, 4, 5, 6]);
+
+        let mut words = ["apple", "dog", "cat", "banana", "elephant", "fox"];
+        pdq_sort_by(&mut words, |a, b| b.cmp(a));
+        assert_eq!(words, ["fox", "elephant", "dog", "cat", "banana", "apple"]);
+
+        let mut records = [("dog", 3.5), ("cat", 1.25), ("fox", 2.0), ("ant", 0.5)];
+        pdq_sort_by_key(&mut records, |&(_, weight)| weight);
+        assert_eq!(
+            records,
+            [("ant", 0.5), ("cat", 1.25), ("fox", 2.0), ("dog", 3.5)]
+        );
+    }
+
+    #[test]
+    fn test_pdq_sort_random() {
+        for (len, modulo) in [(21, 10), (100, 1000), (1_000, 10), (10_000, 1 << 32)] {
+            count_comparisons(&pseudo_random(len, modulo));
+        }
+    }
+
+    #[test]
+    fn test_pdq_sort_sorted_runs_are_linear() {
+        let len = 10_000;
+        let sorted: Vec<u64> = (0..len as u64).collect();
+        let reversed: Vec<u64> = sorted.iter().rev().copied().collect();
+        let mut one_off = sorted.clone();
+        one_off.swap(10, 9_000);
+
+        assert!(count_comparisons(&sorted) < 2 * len);
+        assert!(count_comparisons(&reversed) < 2 * len);
+        assert!(count_comparisons(&one_off) < 4 * len);
+    }
+
+    #[test]
+    fn test_pdq_sort_patterns() {
+        let len: usize = 10_000;
+        let bound = 6 * len * (usize::BITS - len.leading_zeros()) as usize;
+        let half = len as u64 / 2;
+        let patterns: Vec<Vec<u64>> = vec![
+            vec![7; len],
+            (0..len as u64).map(|i| i % 2).collect(),
+            (0..half).chain((0..half).rev()).collect(),
+            (0..len as u64).map(|i| i % 100).collect(),
+            (0..len as u64).map(|i| (i * 7919) % len as u64).collect(),
+        ];
+
+        for nums in patterns {
+            assert!(count_comparisons(&nums) <= bound);
+        }
+    }
+
+    #[test]
+    fn test_partition_in_blocks() {
+        for len in [0, 1, 5, 255, 256, 257, 1_000] {
+            let mut nums = pseudo_random(len, 100);
+            let smaller = nums.iter().filter(|&&x| x < 50).count();
+            let mid =
+                partition_in_blocks(&mut nums, &50, &mut |a: &u64, b: &u64| a.cmp(b), &Silent);
+            assert_eq!(mid, smaller);
+            assert!(nums[..mid].iter().all(|&x| x < 50));
+            assert!(nums[mid..].iter().all(|&x| x >= 50));
+        }
+    }
+
+    #[test]
+    fn test_pdq_sort_panic_safety() {
+        check_panic_safety(|nums, compare| pdq_sort_by(nums, compare));
+    }
+}
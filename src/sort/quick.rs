@@ -1,41 +1,154 @@
+use super::heap::heap_sort_observed;
+use super::insertion::insertion_sort_observed;
+use super::partial_order;
+use super::stats::{swap, Observer, Silent};
+use std::cmp::Ordering;
+
 pub fn quick_sort<T: PartialOrd>(nums: &mut [T]) {
-    let len = nums.len();
-    if len <= 1 {
-        return;
-    }
+    quick_sort_by(nums, partial_order);
+}
 
-    let pivot_index = partition(nums);
+// Introsort: quicksort that switches to heap_sort once recursion goes deeper than 2·log n,
+// so the worst case stays O(n log n) and the stack depth O(log n)
+pub fn quick_sort_by<T, F: FnMut(&T, &T) -> Ordering>(nums: &mut [T], mut compare: F) {
+    quick_sort_observed(nums, &mut compare, &Silent);
+}
+
+pub(crate) fn quick_sort_observed<T, F, O>(nums: &mut [T], compare: &mut F, observer: &O)
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    let limit = 2 * (usize::BITS - nums.len().leading_zeros());
+    sort(nums, limit, compare, observer);
+}
 
-    if pivot_index > 0 {
-        quick_sort(&mut nums[0..pivot_index]);
+pub fn quick_sort_by_key<T, K: PartialOrd, F: FnMut(&T) -> K>(nums: &mut [T], mut key: F) {
+    quick_sort_by(nums, |a, b| partial_order(&key(a), &key(b)));
+}
+
+// Slices this short are finished with insertion sort
+const INSERTION_THRESHOLD: usize = 16;
+// Slices this long take the pivot from a ninther instead of a median of three
+const NINTHER_THRESHOLD: usize = 128;
+
+pub(crate) fn sort<T, F: FnMut(&T, &T) -> Ordering, O: Observer<T>>(
+    mut nums: &mut [T],
+    mut limit: u32,
+    compare: &mut F,
+    observer: &O,
+) {
+    observer.enter();
+    loop {
+        let len = nums.len();
+        if len <= INSERTION_THRESHOLD {
+            insertion_sort_observed(nums, compare, observer);
+            break;
+        }
+
+        // Too many bad partitions, give up on quicksort for this slice
+        if limit == 0 {
+            heap_sort_observed(nums, compare, observer);
+            break;
+        }
+        limit -= 1;
+
+        let pivot = choose_pivot(nums, compare);
+        let (lt, gt) = partition(nums, pivot, compare, observer);
+
+        // Recurse into the smaller side and loop on the larger one
+        let (left, rest) = nums.split_at_mut(lt);
+        let right = &mut rest[gt - lt..];
+        if left.len() < right.len() {
+            sort(left, limit, compare, observer);
+            nums = right;
+        } else {
+            sort(right, limit, compare, observer);
+            nums = left;
+        }
     }
+    observer.leave();
+}
 
-    if pivot_index < len - 1 {
-        quick_sort(&mut nums[pivot_index + 1..]);
+//Index of the median of the three chosen elements
+fn median_of_three<T, F: FnMut(&T, &T) -> Ordering>(
+    nums: &[T],
+    a: usize,
+    b: usize,
+    c: usize,
+    compare: &mut F,
+) -> usize {
+    let less = |x: usize, y: usize, compare: &mut F| compare(&nums[x], &nums[y]) == Ordering::Less;
+    if less(a, b, compare) {
+        if less(b, c, compare) {
+            b
+        } else if less(a, c, compare) {
+            c
+        } else {
+            a
+        }
+    } else if less(a, c, compare) {
+        a
+    } else if less(b, c, compare) {
+        c
+    } else {
+        b
     }
 }
 
-fn partition<T: PartialOrd>(nums: &mut [T]) -> usize {
+//Median of three for short slices, Tukey's ninther (median of three medians) for long ones
+pub(crate) fn choose_pivot<T, F: FnMut(&T, &T) -> Ordering>(nums: &[T], compare: &mut F) -> usize {
     let len = nums.len();
-    let pivot_index = len / 2;
+    let mid = len / 2;
+    if len < NINTHER_THRESHOLD {
+        return median_of_three(nums, 0, mid, len - 1, compare);
+    }
 
-    nums.swap(pivot_index, len - 1); // Move the pivot element to the end
+    let step = len / 8;
+    let low = median_of_three(nums, 0, step, 2 * step, compare);
+    let middle = median_of_three(nums, mid - step, mid, mid + step, compare);
+    let high = median_of_three(nums, len - 1 - 2 * step, len - 1 - step, len - 1, compare);
+    median_of_three(nums, low, middle, high, compare)
+}
+
+// Three way (Dutch flag) partition around nums[pivot_index]
+// Returns (lt, gt) so that nums[..lt] < pivot, nums[lt..gt] == pivot and nums[gt..] > pivot
+pub(crate) fn partition<T, F: FnMut(&T, &T) -> Ordering, O: Observer<T>>(
+    nums: &mut [T],
+    pivot_index: usize,
+    compare: &mut F,
+    observer: &O,
+) -> (usize, usize) {
+    let len = nums.len();
+    swap(nums, 0, pivot_index, observer); // Move the pivot element to the front
 
-    let mut i = 0;
-    for j in 0..len - 1 {
-        if nums[j] <= nums[len - 1] {
-            nums.swap(i, j);
-            i += 1;
+    // nums[1..lt] < pivot, nums[lt..i] == pivot, nums[gt..] > pivot
+    let mut lt = 1;
+    let mut i = 1;
+    let mut gt = len;
+    while i < gt {
+        match compare(&nums[i], &nums[0]) {
+            Ordering::Less => {
+                swap(nums, lt, i, observer);
+                lt += 1;
+                i += 1;
+            }
+            Ordering::Greater => {
+                gt -= 1;
+                swap(nums, i, gt, observer);
+            }
+            Ordering::Equal => i += 1,
         }
     }
 
-    nums.swap(i, len - 1); // Move the pivot element to its final position
-    i
+    swap(nums, 0, lt - 1, observer); // Move the pivot element to its final position
+    (lt - 1, gt)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sort::test_utils::check_by_variants;
 
     #[test]
     fn test_quick_sort_empty() {
@@ -65,4 +178,117 @@ mod tests {
         quick_sort(&mut arr);
         assert_eq!(arr, ["apple", "banana", "cat", "dog", "elephant", "fox"]);
     }
+
+    #[test]
+    fn test_quick_sort_by_variants() {
+        check_by_variants(
+            |nums, compare| quick_sort_by(nums, compare),
+            |records, key| quick_sort_by_key(records, key),
+        );
+    }
+
+    // Sorts a copy of nums, checks the result and returns the number of comparisons
+    fn count_comparisons(nums: &[u32]) -> usize {
+        let mut sorted = nums.to_vec();
+        let mut comparisons = 0;
+        quick_sort_by(&mut sorted, |a, b| {
+            comparisons += 1;
+            a.cmp(b)
+        });
+
+        let mut expected = nums.to_vec();
+        expected.sort();
+        assert_eq!(sorted, expected);
+        comparisons
+    }
+
+    // Generous O(n log n) bound, quadratic behaviour is orders of magnitude above it
+    fn n_log_n(len: usize) -> usize {
+        6 * len * (usize::BITS - len.leading_zeros()) as usize
+    }
+
+    #[test]
+    fn test_quick_sort_patterns() {
+        let len = 10_000;
+        let half = len as u32 / 2;
+        let patterns: Vec<Vec<u32>> = vec![
+            (0..len as u32).collect(),
+            (0..len as u32).rev().collect(),
+            (0..half).chain((0..half).rev()).collect(),
+            (0..len as u32).map(|i| i % 100).collect(),
+            (0..len as u32).map(|i| i % 2).collect(),
+        ];
+
+        for nums in patterns {
+            assert!(count_comparisons(&nums) <= n_log_n(len));
+        }
+    }
+
+    #[test]
+    fn test_quick_sort_equal_keys() {
+        // the three way partition finishes all equal keys in a single pass
+        let nums = vec![7; 10_000];
+        assert!(count_comparisons(&nums) <= 2 * nums.len());
+    }
+
+    #[test]
+    fn test_quick_sort_median_of_three_killer() {
+        // Musser's sequence that makes median of three quicksort quadratic
+        let k = 5_000;
+        let mut nums = vec![0; 2 * k];
+        for i in 1..=k {
+            if i % 2 == 1 {
+                nums[i - 1] = i as u32;
+                nums[i] = (k + i) as u32;
+            }
+            nums[k + i - 1] = 2 * i as u32;
+        }
+        assert!(count_comparisons(&nums) <= n_log_n(nums.len()));
+    }
+
+    #[test]
+    fn test_quick_sort_adversary() {
+        // McIlroy's adversary decides the values lazily so that every pivot is a bad one,
+        // only the heap_sort fallback keeps the comparisons in O(n log n)
+        let len = 10_000;
+        let gas = len;
+        let mut values = vec![gas; len];
+        let mut solid = 0;
+        let mut candidate = 0;
+        let mut comparisons = 0;
+
+        let mut items: Vec<usize> = (0..len).collect();
+        quick_sort_by(&mut items, |&x, &y| {
+            comparisons += 1;
+            if values[x] == gas && values[y] == gas {
+                if x == candidate {
+                    values[x] = solid;
+                } else {
+                    values[y] = solid;
+                }
+                solid += 1;
+            }
+            if values[x] == gas {
+                candidate = x;
+            } else if values[y] == gas {
+                candidate = y;
+            }
+            values[x].cmp(&values[y])
+        });
+
+        assert!(items
+            .windows(2)
+            .all(|pair| values[pair[0]] <= values[pair[1]]));
+        assert!(comparisons <= n_log_n(len));
+    }
+
+    #[test]
+    fn test_partition_three_way() {
+        let mut nums = [3, 1, 3, 5, 3, 0, 4, 3];
+        let (lt, gt) = partition(&mut nums, 0, &mut |a: &i32, b: &i32| a.cmp(b), &Silent);
+        assert_eq!((lt, gt), (2, 6));
+        assert!(nums[..lt].iter().all(|&x| x < 3));
+        assert!(nums[lt..gt].iter().all(|&x| x == 3));
+        assert!(nums[gt..].iter().all(|&x| x > 3));
+    }
 }
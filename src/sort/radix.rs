@@ -0,0 +1,522 @@
+use super::insertion::insertion_sort_by;
+use std::cmp::Ordering;
+use std::ptr;
+
+// Keys that can be sorted byte by byte instead of by comparisons
+// Comparing the bytes in order, with a key that ends first going first, must give the intended order
+// Keys may have different lengths, like byte strings, or all the same, like integers
+pub trait RadixKey {
+    //Number of bytes in the key
+    fn key_len(&self) -> usize;
+
+    //Byte at the chosen position, 0 is the most significant one
+    fn key_byte(&self, index: usize) -> u8;
+}
+
+// Unsigned integers are already ordered by their big endian bytes
+macro_rules! impl_radix_key_unsigned {
+    ($($t:ty),*) => {$(
+        impl RadixKey for $t {
+            fn key_len(&self) -> usize {
+                std::mem::size_of::<$t>()
+            }
+
+            fn key_byte(&self, index: usize) -> u8 {
+                self.to_be_bytes()[index]
+            }
+        }
+    )*};
+}
+
+// Flipping the sign bit moves negative numbers below the positive ones
+macro_rules! impl_radix_key_signed {
+    ($($t:ty => $u:ty),*) => {$(
+        impl RadixKey for $t {
+            fn key_len(&self) -> usize {
+                std::mem::size_of::<$t>()
+            }
+
+            fn key_byte(&self, index: usize) -> u8 {
+                ((*self as $u) ^ (1 << (<$u>::BITS - 1))).to_be_bytes()[index]
+            }
+        }
+    )*};
+}
+
+impl_radix_key_unsigned!(u8, u16, u32, u64, u128, usize);
+impl_radix_key_signed!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128, isize => usize);
+
+impl RadixKey for char {
+    fn key_len(&self) -> usize {
+        4
+    }
+
+    fn key_byte(&self, index: usize) -> u8 {
+        (*self as u32).key_byte(index)
+    }
+}
+
+impl RadixKey for [u8] {
+    fn key_len(&self) -> usize {
+        self.len()
+    }
+
+    fn key_byte(&self, index: usize) -> u8 {
+        self[index]
+    }
+}
+
+impl RadixKey for Vec<u8> {
+    fn key_len(&self) -> usize {
+        self.len()
+    }
+
+    fn key_byte(&self, index: usize) -> u8 {
+        self[index]
+    }
+}
+
+impl RadixKey for str {
+    fn key_len(&self) -> usize {
+        self.len()
+    }
+
+    fn key_byte(&self, index: usize) -> u8 {
+        self.as_bytes()[index]
+    }
+}
+
+impl RadixKey for String {
+    fn key_len(&self) -> usize {
+        self.len()
+    }
+
+    fn key_byte(&self, index: usize) -> u8 {
+        self.as_bytes()[index]
+    }
+}
+
+impl<T: RadixKey + ?Sized> RadixKey for &T {
+    fn key_len(&self) -> usize {
+        (**self).key_len()
+    }
+
+    fn key_byte(&self, index: usize) -> u8 {
+        (**self).key_byte(index)
+    }
+}
+
+// One bucket per byte value plus bucket 0 for keys that already ended
+const BUCKETS: usize = 257;
+// Widest key range counting_sort handles before leaving it to radix_sort
+const COUNTING_LIMIT: u128 = 1 << 20;
+// The range must also stay within this many times the length, or the counts cost more than the keys
+const COUNTING_SPREAD: u128 = 4;
+// Slices this short are finished with insertion sort by msd_radix_sort
+const MSD_INSERTION: usize = 32;
+
+// Bucket of the key at the chosen position
+fn digit<T: RadixKey + ?Sized>(key: &T, index: usize) -> usize {
+    if index < key.key_len() {
+        key.key_byte(index) as usize + 1
+    } else {
+        0
+    }
+}
+
+// Compare two keys byte by byte, starting at the chosen position
+fn compare_keys<T: RadixKey + ?Sized>(a: &T, b: &T, from: usize) -> Ordering {
+    let (len_a, len_b) = (a.key_len(), b.key_len());
+    for index in from..len_a.min(len_b) {
+        match a.key_byte(index).cmp(&b.key_byte(index)) {
+            Ordering::Equal => continue,
+            unequal => return unequal,
+        }
+    }
+    len_a.cmp(&len_b)
+}
+
+// Stable distribution of nums into buckets, digits[i] is the bucket of nums[i]
+// Elements are moved bitwise through scratch, so T does not need to be Copy
+// No user code runs while they are moved, the keys were read into digits beforehand
+// Returns the size of every bucket
+fn scatter<T>(
+    nums: &mut [T],
+    digits: &[usize],
+    buckets: usize,
+    scratch: &mut Vec<T>,
+) -> Vec<usize> {
+    let len = nums.len();
+    let mut counts = vec![0; buckets];
+    for &digit in digits {
+        counts[digit] += 1;
+    }
+
+    let mut offsets = Vec::with_capacity(buckets);
+    let mut total = 0;
+    for &count in &counts {
+        offsets.push(total);
+        total += count;
+    }
+
+    scratch.clear();
+    scratch.reserve(len);
+    let temp = scratch.as_mut_ptr();
+    // SAFETY: the offsets hand out every slot of scratch[..len] exactly once,
+    // then the whole slice is copied back, scratch keeps a len of 0 and never drops anything
+    unsafe {
+        for (i, &digit) in digits.iter().enumerate() {
+            ptr::copy_nonoverlapping(nums.as_ptr().add(i), temp.add(offsets[digit]), 1);
+            offsets[digit] += 1;
+        }
+        ptr::copy_nonoverlapping(temp, nums.as_mut_ptr(), len);
+    }
+    counts
+}
+
+//Stable sort for keys in a small range, O(n + range)
+//Ranges much wider than the input and keys that are not all the same length (or longer than 16 bytes) go to radix_sort
+pub fn counting_sort<T: RadixKey>(nums: &mut [T]) {
+    let len = nums.len();
+    if len < 2 {
+        return;
+    }
+
+    let width = nums[0].key_len();
+    if width > 16 || nums.iter().any(|key| key.key_len() != width) {
+        radix_sort(nums);
+        return;
+    }
+
+    // Same length keys up to 16 bytes keep their order as big endian numbers
+    let ranks: Vec<u128> = nums
+        .iter()
+        .map(|key| (0..width).fold(0, |rank, index| rank << 8 | key.key_byte(index) as u128))
+        .collect();
+    let min = *ranks.iter().min().expect("nums is not empty");
+    let max = *ranks.iter().max().expect("nums is not empty");
+    if max - min >= COUNTING_LIMIT || max - min > COUNTING_SPREAD * len as u128 {
+        radix_sort(nums);
+        return;
+    }
+
+    let digits: Vec<usize> = ranks.iter().map(|&rank| (rank - min) as usize).collect();
+    let mut scratch = Vec::new();
+    scatter(nums, &digits, (max - min) as usize + 1, &mut scratch);
+}
+
+//Stable least significant digit radix sort, one pass per key byte
+//Passes where every key has the same byte are skipped
+pub fn radix_sort<T: RadixKey>(nums: &mut [T]) {
+    let len = nums.len();
+    if len < 2 {
+        return;
+    }
+
+    let max_len = nums.iter().map(RadixKey::key_len).max().unwrap_or(0);
+    let mut digits = vec![0; len];
+    let mut scratch = Vec::new();
+
+    for index in (0..max_len).rev() {
+        for (digit_slot, key) in digits.iter_mut().zip(nums.iter()) {
+            *digit_slot = digit(key, index);
+        }
+        if digits.iter().all(|&d| d == digits[0]) {
+            continue;
+        }
+        scatter(nums, &digits, BUCKETS, &mut scratch);
+    }
+}
+
+//Stable most significant digit radix sort, suited to byte strings
+//Buckets are split on the next byte recursively, short ones finish with insertion sort
+//Only the smaller buckets recurse, so the stack stays O(log n) however long the shared prefixes are
+pub fn msd_radix_sort<T: RadixKey>(nums: &mut [T]) {
+    let mut digits = vec![0; nums.len()];
+    let mut scratch = Vec::new();
+    msd(nums, 0, &mut digits, &mut scratch);
+}
+
+fn msd<T: RadixKey>(
+    mut nums: &mut [T],
+    mut index: usize,
+    mut digits: &mut [usize],
+    scratch: &mut Vec<T>,
+) {
+    loop {
+        let len = nums.len();
+        if len <= MSD_INSERTION {
+            insertion_sort_by(nums, |a, b| compare_keys(a, b, index));
+            return;
+        }
+
+        for (digit_slot, key) in digits.iter_mut().zip(nums.iter()) {
+            *digit_slot = digit(key, index);
+        }
+
+        // Every key shares this byte, move on to the next one without moving anything
+        if digits.iter().all(|&d| d == digits[0]) {
+            if digits[0] == 0 {
+                return;
+            }
+            index += 1;
+            continue;
+        }
+
+        let counts = scatter(nums, digits, BUCKETS, scratch);
+        // Keys that ended are all equal, the other buckets are sorted on the next byte
+        // Every bucket but the largest holds at most half the keys, only those recurse
+        let largest = (1..BUCKETS)
+            .max_by_key(|&bucket| counts[bucket])
+            .expect("there is more than one bucket");
+        let mut start = counts[0];
+        let mut next = (0, 0);
+        for (bucket, &count) in counts.iter().enumerate().skip(1) {
+            let end = start + count;
+            if bucket == largest {
+                next = (start, end);
+            } else if count > 1 {
+                msd(
+                    &mut nums[start..end],
+                    index + 1,
+                    &mut digits[start..end],
+                    scratch,
+                );
+            }
+            start += count;
+        }
+        nums = &mut nums[next.0..next.1];
+        digits = &mut digits[next.0..next.1];
+        index += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::test_utils::{check_stability, check_stability_on, Tagged};
+    use std::cell::Cell;
+    use std::panic::{self, AssertUnwindSafe};
+
+    fn pseudo_random(len: usize) -> Vec<u64> {
+        let mut seed: u64 = 5;
+        (0..len)
+            .map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                seed ^ (seed >> 29)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_counting_sort_small_range() {
+        let mut nums: Vec<i16> = pseudo_random(1_000)
+            .into_iter()
+            .map(|x| (x % 200) as i16 - 100)
+            .collect();
+        let mut expected = nums.clone();
+        expected.sort();
+        counting_sort(&mut nums);
+        assert_eq!(nums, expected);
+
+        let mut bytes = *b"counting";
+        counting_sort(&mut bytes);
+        assert_eq!(&bytes, b"cginnotu");
+
+        let mut empty: [u8; 0] = [];
+        counting_sort(&mut empty);
+        assert_eq!(empty, []);
+    }
+
+    #[test]
+    fn test_counting_sort_falls_back() {
+        let mut nums = pseudo_random(1_000);
+        let mut expected = nums.clone();
+        expected.sort();
+        counting_sort(&mut nums);
+        assert_eq!(nums, expected);
+
+        let mut words = ["pear", "fig", "apple", "fig", ""];
+        counting_sort(&mut words);
+        assert_eq!(words, ["", "apple", "fig", "fig", "pear"]);
+
+        // A range far wider than the input is not worth counting even below COUNTING_LIMIT
+        let mut sparse: Vec<u32> = vec![1 << 19, 3, 1 << 18, 0];
+        counting_sort(&mut sparse);
+        assert_eq!(sparse, vec![0, 3, 1 << 18, 1 << 19]);
+    }
+
+    #[test]
+    fn test_radix_sort_integers() {
+        let random = pseudo_random(2_000);
+
+        macro_rules! check {
+            ($($t:ty),*) => {$(
+                let mut nums: Vec<$t> = random.iter().map(|&x| x as $t).collect();
+                nums.extend([<$t>::MIN, <$t>::MAX, 0 as $t]);
+                let mut expected = nums.clone();
+                expected.sort();
+                radix_sort(&mut nums);
+                assert_eq!(nums, expected, "{}", stringify!($t));
+            )*};
+        }
+        check!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+        let mut chars: Vec<char> = "radix sort ünïcode".chars().collect();
+        let mut expected = chars.clone();
+        expected.sort();
+        radix_sort(&mut chars);
+        assert_eq!(chars, expected);
+    }
+
+    // A tagged record sorted by its key, users implement RadixKey for their own types like this
+    #[derive(Debug, Clone, Copy)]
+    struct Record(Tagged);
+
+    impl RadixKey for Record {
+        fn key_len(&self) -> usize {
+            self.0 .0.key_len()
+        }
+
+        fn key_byte(&self, index: usize) -> u8 {
+            self.0 .0.key_byte(index)
+        }
+    }
+
+    #[test]
+    fn test_radix_sorts_are_stable() {
+        // Keys far apart, so counting_sort leaves them to radix_sort
+        let spread: Vec<u32> = pseudo_random(3_000)
+            .into_iter()
+            .map(|x| (x % 40) as u32 * 1_000_003)
+            .collect();
+
+        let sorts: [fn(&mut [Record]); 3] = [counting_sort, radix_sort, msd_radix_sort];
+        for sort in sorts {
+            let mut sort_records = |records: &mut [Tagged]| {
+                let mut wrapped: Vec<Record> = records.iter().copied().map(Record).collect();
+                sort(&mut wrapped);
+                for (record, Record(sorted)) in records.iter_mut().zip(wrapped) {
+                    *record = sorted;
+                }
+            };
+            check_stability(&mut sort_records).unwrap();
+            check_stability_on(
+                vec![("spread keys".to_string(), spread.clone())],
+                sort_records,
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_msd_radix_sort_strings() {
+        let mut words: Vec<String> = pseudo_random(2_000)
+            .into_iter()
+            .map(|x| format!("{}", x % 100_000).repeat((x % 3) as usize))
+            .collect();
+        words.extend(["".to_string(), "prefix".to_string(), "pre".to_string()]);
+        let mut expected = words.clone();
+        expected.sort();
+
+        msd_radix_sort(&mut words);
+        assert_eq!(words, expected);
+
+        let mut bytes: Vec<Vec<u8>> = expected
+            .iter()
+            .rev()
+            .map(|w| w.as_bytes().to_vec())
+            .collect();
+        msd_radix_sort(&mut bytes);
+        assert!(bytes
+            .iter()
+            .map(|b| b.as_slice())
+            .eq(expected.iter().map(|w| w.as_bytes())));
+
+        let mut slices: Vec<&[u8]> = vec![b"b", b"ab", b"a", b"", b"abc"];
+        radix_sort(&mut slices);
+        assert_eq!(slices, vec![&b""[..], b"a", b"ab", b"abc", b"b"]);
+    }
+
+    #[test]
+    fn test_msd_radix_sort_shared_prefix() {
+        let mut words: Vec<String> = (0..500).rev().map(|i| format!("{:0>40}", i)).collect();
+        let mut expected = words.clone();
+        expected.sort();
+        msd_radix_sort(&mut words);
+        assert_eq!(words, expected);
+
+        let mut same = vec!["same"; 100];
+        msd_radix_sort(&mut same);
+        assert_eq!(same, vec!["same"; 100]);
+    }
+
+    #[test]
+    fn test_msd_radix_sort_nested_prefixes() {
+        // Every key is a prefix of the next, so each byte only splits off a single key
+        let bytes = vec![b'a'; 10_000];
+        let mut keys: Vec<&[u8]> = (0..bytes.len())
+            .map(|i| &bytes[..i * 7_919 % bytes.len()])
+            .collect();
+        msd_radix_sort(&mut keys);
+        assert!(keys.iter().enumerate().all(|(i, key)| key.len() == i));
+    }
+
+    // Key whose bytes run out of budget, counting drops
+    struct Fragile<'a> {
+        key: String,
+        budget: &'a Cell<usize>,
+        drops: &'a Cell<usize>,
+    }
+
+    impl RadixKey for Fragile<'_> {
+        fn key_len(&self) -> usize {
+            self.key.len()
+        }
+
+        fn key_byte(&self, index: usize) -> u8 {
+            if self.budget.get() == 0 {
+                panic!("key gave up");
+            }
+            self.budget.set(self.budget.get() - 1);
+            self.key.as_bytes()[index]
+        }
+    }
+
+    impl Drop for Fragile<'_> {
+        fn drop(&mut self) {
+            self.drops.set(self.drops.get() + 1);
+        }
+    }
+
+    #[test]
+    fn test_radix_sorts_panic_safety() {
+        for sort in ["counting_sort", "radix_sort", "msd_radix_sort"] {
+            for budget in [0, 10, 500, 3_000] {
+                let budget = Cell::new(budget);
+                let drops = Cell::new(0);
+                {
+                    let mut nums: Vec<Fragile> = (0..200)
+                        .map(|i| Fragile {
+                            key: format!("{:03}", i * 7 % 200),
+                            budget: &budget,
+                            drops: &drops,
+                        })
+                        .collect();
+                    let _ = panic::catch_unwind(AssertUnwindSafe(|| match sort {
+                        "counting_sort" => counting_sort(&mut nums),
+                        "radix_sort" => radix_sort(&mut nums),
+                        _ => msd_radix_sort(&mut nums),
+                    }));
+
+                    assert_eq!(drops.get(), 0);
+                    let mut keys: Vec<&str> = nums.iter().map(|item| item.key.as_str()).collect();
+                    keys.sort();
+                    let expected: Vec<String> = (0..200).map(|i| format!("{:03}", i)).collect();
+                    assert_eq!(keys, expected);
+                }
+                assert_eq!(drops.get(), 200);
+            }
+        }
+    }
+}
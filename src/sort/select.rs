@@ -0,0 +1,380 @@
+use super::heap::{heap_sort_by, sift_down};
+use super::insertion::insertion_sort_observed;
+use super::partial_order;
+use super::quick::{choose_pivot, partition};
+use super::stats::Silent;
+use std::cmp::Ordering;
+
+// Selection: put the k-th element in place or find the k smallest/largest without sorting everything
+
+//Reorder nums so that nums[k] is the element a full sort would put there,
+//everything before it is not greater and everything after it not less
+//Returns (before, nums[k], after), panics when k >= nums.len()
+pub fn select_nth_unstable<T: PartialOrd>(
+    nums: &mut [T],
+    k: usize,
+) -> (&mut [T], &mut T, &mut [T]) {
+    select_nth_unstable_by(nums, k, partial_order)
+}
+
+// Introselect: quickselect on the same pivots and three way partition as quick_sort,
+// once BAD_ROUNDS rounds kept more than 3/4 of the slice the pivots come from median of medians,
+// so the worst case stays O(n)
+pub fn select_nth_unstable_by<T, F: FnMut(&T, &T) -> Ordering>(
+    nums: &mut [T],
+    k: usize,
+    mut compare: F,
+) -> (&mut [T], &mut T, &mut [T]) {
+    let len = nums.len();
+    assert!(k < len, "k is {} but the len is {}", k, len);
+    select(nums, k, &mut compare, BAD_ROUNDS);
+
+    let (before, rest) = nums.split_at_mut(k);
+    let (nth, after) = rest.split_first_mut().unwrap();
+    (before, nth, after)
+}
+
+pub fn select_nth_unstable_by_key<T, K: PartialOrd, F: FnMut(&T) -> K>(
+    nums: &mut [T],
+    k: usize,
+    mut key: F,
+) -> (&mut [T], &mut T, &mut [T]) {
+    select_nth_unstable_by(nums, k, |a, b| partial_order(&key(a), &key(b)))
+}
+
+// Slices this short are finished with insertion sort
+const INSERTION_THRESHOLD: usize = 10;
+
+// Quickselect rounds allowed to keep more than 3/4 of the slice
+// All other rounds shrink the slice by a quarter, so the partitions add up to O(n)
+const BAD_ROUNDS: u32 = 4;
+
+//Narrow down to the side holding k until the pivot lands on it, bad_rounds 0 means median of medians only
+fn select<T, F: FnMut(&T, &T) -> Ordering>(
+    mut nums: &mut [T],
+    mut k: usize,
+    compare: &mut F,
+    mut bad_rounds: u32,
+) {
+    loop {
+        let len = nums.len();
+        if len <= INSERTION_THRESHOLD {
+            insertion_sort_observed(nums, compare, &Silent);
+            return;
+        }
+
+        let pivot = if bad_rounds > 0 {
+            choose_pivot(nums, compare)
+        } else {
+            median_of_medians(nums, compare)
+        };
+        let (lt, gt) = partition(nums, pivot, compare, &Silent);
+
+        if k < lt {
+            nums = &mut nums[..lt];
+        } else if k >= gt {
+            nums = &mut nums[gt..];
+            k -= gt;
+        } else {
+            return;
+        }
+        if 4 * nums.len() > 3 * len {
+            bad_rounds = bad_rounds.saturating_sub(1);
+        }
+    }
+}
+
+//Index of a pivot with at least 3/10 of nums on each side
+//Sorts groups of five, gathers their medians at the front and selects the median of those
+fn median_of_medians<T, F: FnMut(&T, &T) -> Ordering>(nums: &mut [T], compare: &mut F) -> usize {
+    let groups = nums.len() / 5;
+    for group in 0..groups {
+        let start = group * 5;
+        insertion_sort_observed(&mut nums[start..start + 5], compare, &Silent);
+        nums.swap(group, start + 2);
+    }
+
+    let mid = groups / 2;
+    select(&mut nums[..groups], mid, compare, 0);
+    mid
+}
+
+//Sort the k smallest elements into nums[..k], the rest is left in no particular order
+//Keeps a max-heap of the k smallest seen so far, O(n log k)
+pub fn partial_sort<T: PartialOrd>(nums: &mut [T], k: usize) {
+    partial_sort_by(nums, k, partial_order);
+}
+
+pub fn partial_sort_by<T, F: FnMut(&T, &T) -> Ordering>(nums: &mut [T], k: usize, mut compare: F) {
+    let k = k.min(nums.len());
+    if k == 0 {
+        return;
+    }
+
+    for i in (0..k / 2).rev() {
+        sift_down(nums, i, k, &mut compare, &Silent);
+    }
+    // Anything smaller than the largest of the heap replaces it
+    for i in k..nums.len() {
+        if compare(&nums[i], &nums[0]) == Ordering::Less {
+            nums.swap(0, i);
+            sift_down(nums, 0, k, &mut compare, &Silent);
+        }
+    }
+    for end in (1..k).rev() {
+        nums.swap(0, end);
+        sift_down(nums, 0, end, &mut compare, &Silent);
+    }
+}
+
+pub fn partial_sort_by_key<T, K: PartialOrd, F: FnMut(&T) -> K>(
+    nums: &mut [T],
+    k: usize,
+    mut key: F,
+) {
+    partial_sort_by(nums, k, |a, b| partial_order(&key(a), &key(b)));
+}
+
+//The k largest items, largest first
+pub fn top_k<T: PartialOrd, I: IntoIterator<Item = T>>(items: I, k: usize) -> Vec<T> {
+    top_k_by(items, k, partial_order)
+}
+
+//Bounded heap of k items, the smallest kept item sits at the root and is the one replaced
+//O(n log k) time and O(k) memory, so items can be a stream
+pub fn top_k_by<T, I, F>(items: I, k: usize, mut compare: F) -> Vec<T>
+where
+    I: IntoIterator<Item = T>,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    if k == 0 {
+        return Vec::new();
+    }
+    // sift_down builds max-heaps, reversing the order keeps the smallest on top
+    let mut reversed = |a: &T, b: &T| compare(b, a);
+    let mut items = items.into_iter();
+    let mut heap: Vec<T> = items.by_ref().take(k).collect();
+    let len = heap.len();
+    for i in (0..len / 2).rev() {
+        sift_down(&mut heap, i, len, &mut reversed, &Silent);
+    }
+
+    for item in items {
+        if reversed(&item, &heap[0]) == Ordering::Less {
+            heap[0] = item;
+            sift_down(&mut heap, 0, len, &mut reversed, &Silent);
+        }
+    }
+
+    heap_sort_by(&mut heap, reversed);
+    heap
+}
+
+pub fn top_k_by_key<T, I, K, F>(items: I, k: usize, mut key: F) -> Vec<T>
+where
+    I: IntoIterator<Item = T>,
+    K: PartialOrd,
+    F: FnMut(&T) -> K,
+{
+    top_k_by(items, k, |a, b| partial_order(&key(a), &key(b)))
+}
+
+//The k smallest items, smallest first
+pub fn bottom_k<T: PartialOrd, I: IntoIterator<Item = T>>(items: I, k: usize) -> Vec<T> {
+    bottom_k_by(items, k, partial_order)
+}
+
+pub fn bottom_k_by<T, I, F>(items: I, k: usize, mut compare: F) -> Vec<T>
+where
+    I: IntoIterator<Item = T>,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    top_k_by(items, k, |a, b| compare(b, a))
+}
+
+pub fn bottom_k_by_key<T, I, K, F>(items: I, k: usize, mut key: F) -> Vec<T>
+where
+    I: IntoIterator<Item = T>,
+    K: PartialOrd,
+    F: FnMut(&T) -> K,
+{
+    bottom_k_by(items, k, |a, b| partial_order(&key(a), &key(b)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn random(len: usize, seed: u64) -> Vec<u32> {
+        let mut seed = seed;
+        (0..len)
+            .map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                (seed >> 40) as u32 % 500
+            })
+            .collect()
+    }
+
+    fn check_nth(nums: &[u32], k: usize, limit: Option<u32>) {
+        let mut sorted = nums.to_vec();
+        sorted.sort();
+        let mut nums = nums.to_vec();
+        match limit {
+            Some(limit) => select(&mut nums, k, &mut u32::cmp, limit),
+            None => {
+                select_nth_unstable(&mut nums, k);
+            }
+        }
+        assert_eq!(nums[k], sorted[k], "k {}", k);
+        assert!(nums[..k].iter().all(|&x| x <= nums[k]));
+        assert!(nums[k + 1..].iter().all(|&x| x >= nums[k]));
+        nums.sort();
+        assert_eq!(nums, sorted);
+    }
+
+    #[test]
+    fn test_select_nth_unstable() {
+        for len in [1, 2, 5, 11, 64, 257] {
+            let nums = random(len, len as u64);
+            for k in 0..len {
+                check_nth(&nums, k, None);
+            }
+        }
+
+        let nums = random(10_000, 3);
+        for k in [0, 1, 4_999, 5_000, 9_998, 9_999] {
+            check_nth(&nums, k, None);
+        }
+
+        let mut nums = [5, 1, 4, 2, 3];
+        let (before, median, after) = select_nth_unstable(&mut nums, 2);
+        assert_eq!(*median, 3);
+        before.sort();
+        after.sort();
+        assert_eq!((before, after), (&mut [1, 2][..], &mut [4, 5][..]));
+    }
+
+    #[test]
+    fn test_median_of_medians_alone() {
+        // limit 0 never uses the quicksort pivots
+        let patterns: [Vec<u32>; 4] = [
+            random(3_000, 7),
+            (0..3_000).collect(),
+            (0..3_000).rev().collect(),
+            vec![9; 3_000],
+        ];
+        for nums in &patterns {
+            for k in [0, 1_234, 1_500, 2_999] {
+                check_nth(nums, k, Some(0));
+            }
+        }
+    }
+
+    // McIlroy's adversary makes every quickselect pivot a bad one, returns the comparisons used
+    fn adversary_comparisons(len: usize) -> usize {
+        let gas = len;
+        let mut values = vec![gas; len];
+        let mut solid = 0;
+        let mut candidate = 0;
+        let mut comparisons = 0;
+
+        let mut items: Vec<usize> = (0..len).collect();
+        let k = len / 2;
+        select_nth_unstable_by(&mut items, k, |&x, &y| {
+            comparisons += 1;
+            if values[x] == gas && values[y] == gas {
+                if x == candidate {
+                    values[x] = solid;
+                } else {
+                    values[y] = solid;
+                }
+                solid += 1;
+            }
+            if values[x] == gas {
+                candidate = x;
+            } else if values[y] == gas {
+                candidate = y;
+            }
+            values[x].cmp(&values[y])
+        });
+
+        let nth = values[items[k]];
+        assert!(items[..k].iter().all(|&x| values[x] <= nth));
+        assert!(items[k + 1..].iter().all(|&x| values[x] >= nth));
+        comparisons
+    }
+
+    #[test]
+    fn test_select_nth_adversary_is_linear() {
+        // median of medians has to take over after a constant number of bad rounds,
+        // so comparisons per element stay flat instead of growing with log n
+        let per_element: Vec<f64> = [1_000, 10_000, 100_000]
+            .into_iter()
+            .map(|len| adversary_comparisons(len) as f64 / len as f64)
+            .collect();
+        assert!(
+            per_element.iter().all(|&ratio| ratio < 20.0),
+            "{:?}",
+            per_element
+        );
+        assert!(per_element[2] < per_element[0] * 1.25, "{:?}", per_element);
+    }
+
+    #[test]
+    #[should_panic(expected = "k is 3 but the len is 3")]
+    fn test_select_nth_out_of_bounds() {
+        select_nth_unstable(&mut [1, 2, 3], 3);
+    }
+
+    #[test]
+    fn test_select_nth_by_key() {
+        let mut people = [("ann", 31), ("bob", 25), ("cid", 47), ("dee", 19)];
+        let (_, oldest, _) = select_nth_unstable_by_key(&mut people, 3, |&(_, age)| age);
+        assert_eq!(oldest.0, "cid");
+        let (_, youngest, _) = select_nth_unstable_by(&mut people, 3, |a, b| b.1.cmp(&a.1));
+        assert_eq!(youngest.0, "dee");
+    }
+
+    #[test]
+    fn test_partial_sort() {
+        let nums = random(1_000, 11);
+        let mut sorted = nums.clone();
+        sorted.sort();
+        for k in [0, 1, 10, 999, 1_000, 2_000] {
+            let mut partial = nums.clone();
+            partial_sort(&mut partial, k);
+            let k = k.min(nums.len());
+            assert_eq!(partial[..k], sorted[..k]);
+            partial.sort();
+            assert_eq!(partial, sorted);
+        }
+
+        let mut words = ["pear", "fig", "banana", "kiwi", "apple"];
+        partial_sort_by_key(&mut words, 2, |w| w.len());
+        assert_eq!(words[..2], ["fig", "pear"]);
+        partial_sort_by(&mut words, 1, |a, b| b.cmp(a));
+        assert_eq!(words[0], "pear");
+    }
+
+    #[test]
+    fn test_top_k_and_bottom_k() {
+        let nums = random(5_000, 13);
+        let mut sorted = nums.clone();
+        sorted.sort();
+
+        let top = top_k(nums.iter().copied(), 100);
+        let expected: Vec<u32> = sorted.iter().rev().take(100).copied().collect();
+        assert_eq!(top, expected);
+        assert_eq!(bottom_k(nums.iter().copied(), 100), sorted[..100]);
+
+        assert_eq!(top_k(vec![3, 1, 2], 10), vec![3, 2, 1]);
+        assert_eq!(bottom_k(vec![3, 1, 2], 0), Vec::<i32>::new());
+        assert_eq!(top_k(Vec::<i32>::new(), 3), Vec::<i32>::new());
+
+        let words = ["pear", "fig", "banana", "kiwi"];
+        assert_eq!(top_k_by_key(words, 2, |w| w.len()), ["banana", "pear"]);
+        assert_eq!(bottom_k_by_key(words, 1, |w| w.len()), ["fig"]);
+        assert_eq!(top_k_by(words, 1, |a, b| b.cmp(a)), ["banana"]);
+        assert_eq!(bottom_k_by(words, 1, |a, b| b.cmp(a)), ["pear"]);
+    }
+}
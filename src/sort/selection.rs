@@ -1,20 +1,41 @@
+use super::partial_order;
+use super::stats::{swap, Observer, Silent};
+use std::cmp::Ordering;
+
 pub fn selection_sort<T: PartialOrd>(nums: &mut [T]) {
+    selection_sort_by(nums, partial_order);
+}
+
+pub fn selection_sort_by<T, F: FnMut(&T, &T) -> Ordering>(nums: &mut [T], mut compare: F) {
+    selection_sort_observed(nums, &mut compare, &Silent);
+}
+
+pub(crate) fn selection_sort_observed<T, F, O>(nums: &mut [T], compare: &mut F, observer: &O)
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
     let len = nums.len();
 
     for i in 0..len {
         if let Some((min_idx, _)) = nums[i..]
             .iter()
             .enumerate()
-            .min_by(|&(_, a), &(_, b)| a.partial_cmp(b).unwrap())
+            .min_by(|&(_, a), &(_, b)| compare(a, b))
         {
-            nums.swap(i, i + min_idx);
+            swap(nums, i, i + min_idx, observer);
         }
     }
 }
 
+pub fn selection_sort_by_key<T, K: PartialOrd, F: FnMut(&T) -> K>(nums: &mut [T], mut key: F) {
+    selection_sort_by(nums, |a, b| partial_order(&key(a), &key(b)));
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sort::test_utils::check_by_variants;
 
     #[test]
     fn test_selection_sort_i32() {
@@ -50,4 +71,12 @@ mod tests {
         selection_sort(&mut nums);
         assert_eq!(nums, vec![5]);
     }
+
+    #[test]
+    fn test_selection_sort_by_variants() {
+        check_by_variants(
+            |nums, compare| selection_sort_by(nums, compare),
+            |records, key| selection_sort_by_key(records, key),
+        );
+    }
 }
@@ -1,30 +1,54 @@
-pub fn shell_sort<T: PartialOrd + Copy>(nums: &mut [T]) {
+use super::partial_order;
+use super::stats::{swap, Observer, Silent};
+use std::cmp::Ordering;
+
+pub fn shell_sort<T: PartialOrd>(nums: &mut [T]) {
+    shell_sort_by(nums, partial_order);
+}
+
+pub fn shell_sort_by<T, F: FnMut(&T, &T) -> Ordering>(nums: &mut [T], mut compare: F) {
+    shell_sort_observed(nums, &mut compare, &Silent);
+}
+
+pub(crate) fn shell_sort_observed<T, F, O>(nums: &mut [T], compare: &mut F, observer: &O)
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
     let len = nums.len();
     let mut gap = len / 2;
 
     while gap > 0 {
         for start in 0..gap {
-            insertion_sort_gap(nums, start, gap);
+            insertion_sort_gap(nums, start, gap, compare, observer);
         }
 
         gap /= 2;
     }
 }
 
-fn insertion_sort_gap<T: PartialOrd + Copy>(nums: &mut [T], start: usize, gap: usize) {
+pub fn shell_sort_by_key<T, K: PartialOrd, F: FnMut(&T) -> K>(nums: &mut [T], mut key: F) {
+    shell_sort_by(nums, |a, b| partial_order(&key(a), &key(b)));
+}
+
+fn insertion_sort_gap<T, F: FnMut(&T, &T) -> Ordering, O: Observer<T>>(
+    nums: &mut [T],
+    start: usize,
+    gap: usize,
+    compare: &mut F,
+    observer: &O,
+) {
     let len = nums.len();
     let mut i = start + gap;
 
     while i < len {
-        let curr = nums[i];
         let mut pos = i;
 
-        while pos >= gap && curr < nums[pos - gap] {
-            nums.swap(pos, pos - gap);
+        while pos >= gap && compare(&nums[pos], &nums[pos - gap]) == Ordering::Less {
+            swap(nums, pos, pos - gap, observer);
             pos -= gap;
         }
 
-        nums[pos] = curr;
         i += gap;
     }
 }
@@ -32,6 +56,7 @@ fn insertion_sort_gap<T: PartialOrd + Copy>(nums: &mut [T], start: usize, gap: u
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sort::test_utils::{check_by_variants, check_panic_safety};
 
     #[test]
     fn test_shell_sort_empty_collection() {
@@ -64,4 +89,27 @@ mod tests {
         shell_sort(&mut nums);
         assert_eq!(nums, [2.0, 3.0, 4.0, 5.0, 8.0]);
     }
+
+    #[test]
+    fn test_shell_sort_by_variants() {
+        check_by_variants(
+            |nums, compare| shell_sort_by(nums, compare),
+            |records, key| shell_sort_by_key(records, key),
+        );
+    }
+
+    #[test]
+    fn test_shell_sort_heap_owning() {
+        let mut words: Vec<String> = ["pear", "apple", "fig", "kiwi", "banana"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        shell_sort(&mut words);
+        assert_eq!(words, ["apple", "banana", "fig", "kiwi", "pear"]);
+    }
+
+    #[test]
+    fn test_shell_sort_panic_safety() {
+        check_panic_safety(|nums, compare| shell_sort_by(nums, compare));
+    }
 }
@@ -0,0 +1,724 @@
+use super::bubble::{
+    bubble_sort_by, bubble_sort_observed, cocktail_sort_by, cocktail_sort_observed, comb_sort_by,
+    comb_sort_observed,
+};
+use super::heap::{heap_sort_by, heap_sort_observed};
+use super::insertion::{
+    binary_insertion_sort_by, binary_insertion_sort_from, insertion_sort_by,
+    insertion_sort_observed,
+};
+use super::merge::{merge_sort_by, merge_sort_observed};
+use super::partial_order;
+use super::pdq::{pdq_sort_by, pdq_sort_observed};
+use super::quick::{quick_sort_by, quick_sort_observed};
+use super::selection::{selection_sort_by, selection_sort_observed};
+use super::shell::{shell_sort_by, shell_sort_observed};
+use super::stats::{observe, Observer};
+use super::tim::{tim_sort_by, tim_sort_observed};
+use std::cmp::Ordering;
+use std::fmt;
+
+// Common interface for the sorting algorithms so they can be swapped at runtime
+// Only sort_by has to be written, sort and sort_by_key are built on top of it
+pub trait Sorter {
+    fn name(&self) -> &'static str;
+
+    //Equal elements keep their relative order
+    fn is_stable(&self) -> bool;
+
+    //Needs no more than O(log n) extra memory
+    fn is_in_place(&self) -> bool;
+
+    //Worst case running time
+    fn worst_case(&self) -> Complexity;
+
+    fn sort_by<T, F: FnMut(&T, &T) -> Ordering>(&self, nums: &mut [T], compare: F);
+
+    fn sort<T: PartialOrd>(&self, nums: &mut [T]) {
+        self.sort_by(nums, partial_order);
+    }
+
+    fn sort_by_key<T, K: PartialOrd, F: FnMut(&T) -> K>(&self, nums: &mut [T], mut key: F) {
+        self.sort_by(nums, |a, b| partial_order(&key(a), &key(b)));
+    }
+
+    //Sort and report every step to observer, see stats::sort_stats
+    //The default can only see the comparisons, the sorts of this crate report everything
+    fn sort_observed<T, F, O>(&self, nums: &mut [T], compare: F, observer: &O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer<T>,
+    {
+        self.sort_by(nums, observe(compare, observer));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Complexity {
+    NLogN,
+    Quadratic,
+}
+
+impl fmt::Display for Complexity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Complexity::NLogN => write!(f, "O(n log n)"),
+            Complexity::Quadratic => write!(f, "O(n^2)"),
+        }
+    }
+}
+
+// One unit struct per algorithm
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BubbleSort;
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CocktailSort;
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CombSort;
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeapSort;
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InsertionSort;
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BinaryInsertionSort;
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MergeSort;
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PdqSort;
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QuickSort;
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SelectionSort;
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ShellSort;
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TimSort;
+
+impl Sorter for BubbleSort {
+    fn name(&self) -> &'static str {
+        "bubble_sort"
+    }
+
+    fn is_stable(&self) -> bool {
+        true
+    }
+
+    fn is_in_place(&self) -> bool {
+        true
+    }
+
+    fn worst_case(&self) -> Complexity {
+        Complexity::Quadratic
+    }
+
+    fn sort_by<T, F: FnMut(&T, &T) -> Ordering>(&self, nums: &mut [T], compare: F) {
+        bubble_sort_by(nums, compare);
+    }
+
+    fn sort_observed<T, F, O>(&self, nums: &mut [T], compare: F, observer: &O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer<T>,
+    {
+        bubble_sort_observed(nums, &mut observe(compare, observer), observer);
+    }
+}
+
+impl Sorter for CocktailSort {
+    fn name(&self) -> &'static str {
+        "cocktail_sort"
+    }
+
+    fn is_stable(&self) -> bool {
+        true
+    }
+
+    fn is_in_place(&self) -> bool {
+        true
+    }
+
+    fn worst_case(&self) -> Complexity {
+        Complexity::Quadratic
+    }
+
+    fn sort_by<T, F: FnMut(&T, &T) -> Ordering>(&self, nums: &mut [T], compare: F) {
+        cocktail_sort_by(nums, compare);
+    }
+
+    fn sort_observed<T, F, O>(&self, nums: &mut [T], compare: F, observer: &O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer<T>,
+    {
+        cocktail_sort_observed(nums, &mut observe(compare, observer), observer);
+    }
+}
+
+impl Sorter for CombSort {
+    fn name(&self) -> &'static str {
+        "comb_sort"
+    }
+
+    //Swaps across a gap can jump over equal elements
+    fn is_stable(&self) -> bool {
+        false
+    }
+
+    fn is_in_place(&self) -> bool {
+        true
+    }
+
+    fn worst_case(&self) -> Complexity {
+        Complexity::Quadratic
+    }
+
+    fn sort_by<T, F: FnMut(&T, &T) -> Ordering>(&self, nums: &mut [T], compare: F) {
+        comb_sort_by(nums, compare);
+    }
+
+    fn sort_observed<T, F, O>(&self, nums: &mut [T], compare: F, observer: &O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer<T>,
+    {
+        comb_sort_observed(nums, &mut observe(compare, observer), observer);
+    }
+}
+
+impl Sorter for HeapSort {
+    fn name(&self) -> &'static str {
+        "heap_sort"
+    }
+
+    fn is_stable(&self) -> bool {
+        false
+    }
+
+    fn is_in_place(&self) -> bool {
+        true
+    }
+
+    fn worst_case(&self) -> Complexity {
+        Complexity::NLogN
+    }
+
+    fn sort_by<T, F: FnMut(&T, &T) -> Ordering>(&self, nums: &mut [T], compare: F) {
+        heap_sort_by(nums, compare);
+    }
+
+    fn sort_observed<T, F, O>(&self, nums: &mut [T], compare: F, observer: &O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer<T>,
+    {
+        heap_sort_observed(nums, &mut observe(compare, observer), observer);
+    }
+}
+
+impl Sorter for InsertionSort {
+    fn name(&self) -> &'static str {
+        "insertion_sort"
+    }
+
+    fn is_stable(&self) -> bool {
+        true
+    }
+
+    fn is_in_place(&self) -> bool {
+        true
+    }
+
+    fn worst_case(&self) -> Complexity {
+        Complexity::Quadratic
+    }
+
+    fn sort_by<T, F: FnMut(&T, &T) -> Ordering>(&self, nums: &mut [T], compare: F) {
+        insertion_sort_by(nums, compare);
+    }
+
+    fn sort_observed<T, F, O>(&self, nums: &mut [T], compare: F, observer: &O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer<T>,
+    {
+        insertion_sort_observed(nums, &mut observe(compare, observer), observer);
+    }
+}
+
+impl Sorter for BinaryInsertionSort {
+    fn name(&self) -> &'static str {
+        "binary_insertion_sort"
+    }
+
+    fn is_stable(&self) -> bool {
+        true
+    }
+
+    fn is_in_place(&self) -> bool {
+        true
+    }
+
+    //Only O(n log n) comparisons, but the rotations still move O(n^2) elements
+    fn worst_case(&self) -> Complexity {
+        Complexity::Quadratic
+    }
+
+    fn sort_by<T, F: FnMut(&T, &T) -> Ordering>(&self, nums: &mut [T], compare: F) {
+        binary_insertion_sort_by(nums, compare);
+    }
+
+    fn sort_observed<T, F, O>(&self, nums: &mut [T], compare: F, observer: &O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer<T>,
+    {
+        binary_insertion_sort_from(nums, 1, &mut observe(compare, observer), observer);
+    }
+}
+
+impl Sorter for MergeSort {
+    fn name(&self) -> &'static str {
+        "merge_sort"
+    }
+
+    //merge takes from the left half on ties
+    fn is_stable(&self) -> bool {
+        true
+    }
+
+    fn is_in_place(&self) -> bool {
+        false
+    }
+
+    fn worst_case(&self) -> Complexity {
+        Complexity::NLogN
+    }
+
+    fn sort_by<T, F: FnMut(&T, &T) -> Ordering>(&self, nums: &mut [T], compare: F) {
+        merge_sort_by(nums, compare);
+    }
+
+    fn sort_observed<T, F, O>(&self, nums: &mut [T], compare: F, observer: &O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer<T>,
+    {
+        merge_sort_observed(nums, &mut observe(compare, observer), observer);
+    }
+}
+
+impl Sorter for PdqSort {
+    fn name(&self) -> &'static str {
+        "pdq_sort"
+    }
+
+    fn is_stable(&self) -> bool {
+        false
+    }
+
+    fn is_in_place(&self) -> bool {
+        true
+    }
+
+    fn worst_case(&self) -> Complexity {
+        Complexity::NLogN
+    }
+
+    fn sort_by<T, F: FnMut(&T, &T) -> Ordering>(&self, nums: &mut [T], compare: F) {
+        pdq_sort_by(nums, compare);
+    }
+
+    fn sort_observed<T, F, O>(&self, nums: &mut [T], compare: F, observer: &O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer<T>,
+    {
+        pdq_sort_observed(nums, &mut observe(compare, observer), observer);
+    }
+}
+
+impl Sorter for QuickSort {
+    fn name(&self) -> &'static str {
+        "quick_sort"
+    }
+
+    fn is_stable(&self) -> bool {
+        false
+    }
+
+    fn is_in_place(&self) -> bool {
+        true
+    }
+
+    //Falls back to heap_sort on bad pivots
+    fn worst_case(&self) -> Complexity {
+        Complexity::NLogN
+    }
+
+    fn sort_by<T, F: FnMut(&T, &T) -> Ordering>(&self, nums: &mut [T], compare: F) {
+        quick_sort_by(nums, compare);
+    }
+
+    fn sort_observed<T, F, O>(&self, nums: &mut [T], compare: F, observer: &O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer<T>,
+    {
+        quick_sort_observed(nums, &mut observe(compare, observer), observer);
+    }
+}
+
+impl Sorter for SelectionSort {
+    fn name(&self) -> &'static str {
+        "selection_sort"
+    }
+
+    //Swapping the minimum forward can jump over equal elements
+    fn is_stable(&self) -> bool {
+        false
+    }
+
+    fn is_in_place(&self) -> bool {
+        true
+    }
+
+    fn worst_case(&self) -> Complexity {
+        Complexity::Quadratic
+    }
+
+    fn sort_by<T, F: FnMut(&T, &T) -> Ordering>(&self, nums: &mut [T], compare: F) {
+        selection_sort_by(nums, compare);
+    }
+
+    fn sort_observed<T, F, O>(&self, nums: &mut [T], compare: F, observer: &O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer<T>,
+    {
+        selection_sort_observed(nums, &mut observe(compare, observer), observer);
+    }
+}
+
+impl Sorter for ShellSort {
+    fn name(&self) -> &'static str {
+        "shell_sort"
+    }
+
+    fn is_stable(&self) -> bool {
+        false
+    }
+
+    fn is_in_place(&self) -> bool {
+        true
+    }
+
+    //Halving gaps, other gap sequences do better
+    fn worst_case(&self) -> Complexity {
+        Complexity::Quadratic
+    }
+
+    fn sort_by<T, F: FnMut(&T, &T) -> Ordering>(&self, nums: &mut [T], compare: F) {
+        shell_sort_by(nums, compare);
+    }
+
+    fn sort_observed<T, F, O>(&self, nums: &mut [T], compare: F, observer: &O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer<T>,
+    {
+        shell_sort_observed(nums, &mut observe(compare, observer), observer);
+    }
+}
+
+impl Sorter for TimSort {
+    fn name(&self) -> &'static str {
+        "tim_sort"
+    }
+
+    fn is_stable(&self) -> bool {
+        true
+    }
+
+    //Merges need room for half of the slice
+    fn is_in_place(&self) -> bool {
+        false
+    }
+
+    fn worst_case(&self) -> Complexity {
+        Complexity::NLogN
+    }
+
+    fn sort_by<T, F: FnMut(&T, &T) -> Ordering>(&self, nums: &mut [T], compare: F) {
+        tim_sort_by(nums, compare);
+    }
+
+    fn sort_observed<T, F, O>(&self, nums: &mut [T], compare: F, observer: &O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer<T>,
+    {
+        tim_sort_observed(nums, &mut observe(compare, observer), observer);
+    }
+}
+
+// Registry of every sorter in the crate
+// The trait has generic methods so it can't be a trait object, the enum picks one at runtime instead
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Algorithm {
+    Bubble,
+    Cocktail,
+    Comb,
+    Heap,
+    Insertion,
+    BinaryInsertion,
+    Merge,
+    Pdq,
+    Quick,
+    Selection,
+    Shell,
+    Tim,
+}
+
+// Runs the body with the unit struct behind an Algorithm bound to $sorter
+macro_rules! dispatch {
+    ($algorithm:expr, $sorter:ident => $body:expr) => {
+        match $algorithm {
+            Algorithm::Bubble => {
+                let $sorter = BubbleSort;
+                $body
+            }
+            Algorithm::Cocktail => {
+                let $sorter = CocktailSort;
+                $body
+            }
+            Algorithm::Comb => {
+                let $sorter = CombSort;
+                $body
+            }
+            Algorithm::Heap => {
+                let $sorter = HeapSort;
+                $body
+            }
+            Algorithm::Insertion => {
+                let $sorter = InsertionSort;
+                $body
+            }
+            Algorithm::BinaryInsertion => {
+                let $sorter = BinaryInsertionSort;
+                $body
+            }
+            Algorithm::Merge => {
+                let $sorter = MergeSort;
+                $body
+            }
+            Algorithm::Pdq => {
+                let $sorter = PdqSort;
+                $body
+            }
+            Algorithm::Quick => {
+                let $sorter = QuickSort;
+                $body
+            }
+            Algorithm::Selection => {
+                let $sorter = SelectionSort;
+                $body
+            }
+            Algorithm::Shell => {
+                let $sorter = ShellSort;
+                $body
+            }
+            Algorithm::Tim => {
+                let $sorter = TimSort;
+                $body
+            }
+        }
+    };
+}
+
+impl Algorithm {
+    pub const ALL: [Algorithm; 12] = [
+        Algorithm::Bubble,
+        Algorithm::Cocktail,
+        Algorithm::Comb,
+        Algorithm::Heap,
+        Algorithm::Insertion,
+        Algorithm::BinaryInsertion,
+        Algorithm::Merge,
+        Algorithm::Pdq,
+        Algorithm::Quick,
+        Algorithm::Selection,
+        Algorithm::Shell,
+        Algorithm::Tim,
+    ];
+
+    //Look up a sorter by its name
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.name() == name)
+    }
+}
+
+impl Sorter for Algorithm {
+    fn name(&self) -> &'static str {
+        dispatch!(self, sorter => sorter.name())
+    }
+
+    fn is_stable(&self) -> bool {
+        dispatch!(self, sorter => sorter.is_stable())
+    }
+
+    fn is_in_place(&self) -> bool {
+        dispatch!(self, sorter => sorter.is_in_place())
+    }
+
+    fn worst_case(&self) -> Complexity {
+        dispatch!(self, sorter => sorter.worst_case())
+    }
+
+    fn sort_by<T, F: FnMut(&T, &T) -> Ordering>(&self, nums: &mut [T], compare: F) {
+        dispatch!(self, sorter => sorter.sort_by(nums, compare))
+    }
+
+    fn sort_observed<T, F, O>(&self, nums: &mut [T], compare: F, observer: &O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer<T>,
+    {
+        dispatch!(self, sorter => sorter.sort_observed(nums, compare, observer))
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::test_utils::{check_observer_panic_safety, check_stability};
+    use std::collections::HashSet;
+
+    fn pseudo_random(len: usize, modulo: u64) -> Vec<u64> {
+        let mut seed: u64 = 7;
+        (0..len)
+            .map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                (seed >> 33) % modulo
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_every_sorter_sorts() {
+        let values = pseudo_random(500, 100);
+        let mut expected = values.clone();
+        expected.sort();
+        let mut with_nan: Vec<f64> = values.iter().map(|&x| x as f64 + 0.5).collect();
+        with_nan[250] = f64::NAN;
+        let mut with_nan_sorted = with_nan.clone();
+        with_nan_sorted.sort_by(f64::total_cmp);
+
+        for algorithm in Algorithm::ALL {
+            let mut nums = values.clone();
+            algorithm.sort(&mut nums);
+            assert_eq!(nums, expected, "{} failed", algorithm);
+
+            let mut nums = values.clone();
+            algorithm.sort_by(&mut nums, |a, b| b.cmp(a));
+            assert!(
+                nums.iter().eq(expected.iter().rev()),
+                "{} failed",
+                algorithm
+            );
+
+            let mut empty: Vec<u64> = Vec::new();
+            algorithm.sort(&mut empty);
+            assert!(empty.is_empty());
+
+            // NaN makes the order inconsistent, the result need not be sorted but must keep every element
+            let mut nums = with_nan.clone();
+            algorithm.sort(&mut nums);
+            nums.sort_by(f64::total_cmp);
+            assert_eq!(nums.len(), with_nan_sorted.len(), "{} failed", algorithm);
+            assert!(
+                nums.iter()
+                    .zip(&with_nan_sorted)
+                    .all(|(a, b)| a.total_cmp(b).is_eq()),
+                "{} failed",
+                algorithm
+            );
+        }
+    }
+
+    #[test]
+    fn test_every_sorter_sorts_strings() {
+        let words = ["pear", "apple", "fig", "kiwi", "banana"];
+        for algorithm in Algorithm::ALL {
+            let mut nums: Vec<String> = words.iter().map(|s| s.to_string()).collect();
+            algorithm.sort_by_key(&mut nums, |s| s.len());
+            let lens: Vec<usize> = nums.iter().map(|s| s.len()).collect();
+            assert_eq!(lens, vec![3, 4, 4, 5, 6], "{} failed", algorithm);
+        }
+    }
+
+    #[test]
+    fn test_declared_stability_holds() {
+        for algorithm in Algorithm::ALL {
+            let result = check_stability(|records| algorithm.sort_by_key(records, |&(key, _)| key));
+            // the unstable ones must also really reorder equal keys somewhere, or they could be declared stable
+            assert_eq!(
+                result.is_ok(),
+                algorithm.is_stable(),
+                "{}: {:?}",
+                algorithm,
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn test_every_sorter_survives_a_panicking_observer() {
+        for algorithm in Algorithm::ALL {
+            check_observer_panic_safety(|nums, observer| {
+                algorithm.sort_observed(nums, |a, b| a.key.cmp(&b.key), observer)
+            });
+        }
+    }
+
+    #[test]
+    fn test_registry_names() {
+        let names: HashSet<&str> = Algorithm::ALL.iter().map(Sorter::name).collect();
+        assert_eq!(names.len(), Algorithm::ALL.len());
+
+        for algorithm in Algorithm::ALL {
+            assert_eq!(Algorithm::from_name(algorithm.name()), Some(algorithm));
+        }
+        assert_eq!(Algorithm::from_name("bogo_sort"), None);
+        assert_eq!(Algorithm::Merge.to_string(), "merge_sort");
+    }
+
+    #[test]
+    fn test_metadata() {
+        assert!(InsertionSort.is_stable());
+        assert!(MergeSort.is_stable());
+        assert!(!HeapSort.is_stable());
+        assert!(!MergeSort.is_in_place());
+        assert_eq!(HeapSort.worst_case(), Complexity::NLogN);
+        assert_eq!(Algorithm::Bubble.worst_case().to_string(), "O(n^2)");
+        assert_eq!(Complexity::NLogN.to_string(), "O(n log n)");
+    }
+
+    #[test]
+    fn test_generic_over_sorter() {
+        fn sorted<S: Sorter>(sorter: S, mut nums: Vec<i32>) -> Vec<i32> {
+            sorter.sort(&mut nums);
+            nums
+        }
+
+        assert_eq!(sorted(QuickSort, vec![3, 1, 2]), vec![1, 2, 3]);
+        assert_eq!(sorted(Algorithm::Shell, vec![3, 1, 2]), vec![1, 2, 3]);
+    }
+}
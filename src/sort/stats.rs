@@ -0,0 +1,306 @@
+use super::partial_order;
+use super::sorter::Sorter;
+use std::cell::Cell;
+use std::cmp::Ordering;
+
+// Instrumentation for the sorts: every algorithm reports what it does to an Observer
+// The plain sorts run with Silent, whose hooks are empty and compile away,
+// so only the instrumented path pays for counting
+
+// Hooks called while sorting, every one does nothing by default
+// They take &self so the observer can be shared with the comparator, use Cell to count
+pub trait Observer<T> {
+    //Before every call to the comparator
+    fn compare(&self, _a: &T, _b: &T) {}
+
+    //After two slots were swapped
+    fn swap(&self, _a: &T, _b: &T) {}
+
+    //After an element was moved into slot, which is in the slice or in an auxiliary buffer
+    fn write(&self, _slot: &T) {}
+
+    //When a buffer for len elements is allocated
+    fn alloc(&self, _len: usize) {}
+
+    //When a recursive call starts
+    fn enter(&self) {}
+
+    //When a recursive call returns
+    fn leave(&self) {}
+}
+
+// The observer of the plain sorts
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Silent;
+
+impl<T> Observer<T> for Silent {}
+
+//Comparator that reports every call to observer first
+pub(crate) fn observe<'a, T, F, O>(
+    mut compare: F,
+    observer: &'a O,
+) -> impl FnMut(&T, &T) -> Ordering + 'a
+where
+    F: FnMut(&T, &T) -> Ordering + 'a,
+    O: Observer<T>,
+{
+    move |a, b| {
+        observer.compare(a, b);
+        compare(a, b)
+    }
+}
+
+//nums.swap that reports to observer
+pub(crate) fn swap<T, O: Observer<T>>(nums: &mut [T], a: usize, b: usize, observer: &O) {
+    nums.swap(a, b);
+    observer.swap(&nums[a], &nums[b]);
+}
+
+//nums.reverse that reports each pair it swaps
+pub(crate) fn reverse<T, O: Observer<T>>(nums: &mut [T], observer: &O) {
+    nums.reverse();
+    let len = nums.len();
+    for i in 0..len / 2 {
+        observer.swap(&nums[i], &nums[len - 1 - i]);
+    }
+}
+
+// What a sort did, counted by sort_stats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct SortStats {
+    pub comparisons: u64,
+    pub swaps: u64,
+    //Elements moved one way, a swap is not counted here
+    pub writes: u64,
+    //Auxiliary buffers allocated and the elements they hold in total
+    pub allocations: u64,
+    pub allocated_elements: u64,
+    //Deepest nesting of recursive calls, 0 for the iterative sorts
+    pub max_depth: u32,
+}
+
+#[derive(Default)]
+struct Counter {
+    comparisons: Cell<u64>,
+    swaps: Cell<u64>,
+    writes: Cell<u64>,
+    allocations: Cell<u64>,
+    allocated_elements: Cell<u64>,
+    depth: Cell<u32>,
+    max_depth: Cell<u32>,
+}
+
+fn increment(cell: &Cell<u64>, by: u64) {
+    cell.set(cell.get() + by);
+}
+
+impl<T> Observer<T> for Counter {
+    fn compare(&self, _a: &T, _b: &T) {
+        increment(&self.comparisons, 1);
+    }
+
+    fn swap(&self, _a: &T, _b: &T) {
+        increment(&self.swaps, 1);
+    }
+
+    fn write(&self, _slot: &T) {
+        increment(&self.writes, 1);
+    }
+
+    fn alloc(&self, len: usize) {
+        increment(&self.allocations, 1);
+        increment(&self.allocated_elements, len as u64);
+    }
+
+    fn enter(&self) {
+        let depth = self.depth.get() + 1;
+        self.depth.set(depth);
+        self.max_depth.set(self.max_depth.get().max(depth));
+    }
+
+    fn leave(&self) {
+        self.depth.set(self.depth.get() - 1);
+    }
+}
+
+impl Counter {
+    fn stats(&self) -> SortStats {
+        SortStats {
+            comparisons: self.comparisons.get(),
+            swaps: self.swaps.get(),
+            writes: self.writes.get(),
+            allocations: self.allocations.get(),
+            allocated_elements: self.allocated_elements.get(),
+            max_depth: self.max_depth.get(),
+        }
+    }
+}
+
+//Sort nums with sorter and count what it did
+pub fn sort_stats<T: PartialOrd, S: Sorter>(sorter: &S, nums: &mut [T]) -> SortStats {
+    sort_stats_by(sorter, nums, partial_order)
+}
+
+pub fn sort_stats_by<T, S: Sorter, F: FnMut(&T, &T) -> Ordering>(
+    sorter: &S,
+    nums: &mut [T],
+    compare: F,
+) -> SortStats {
+    let counter = Counter::default();
+    sorter.sort_observed(nums, compare, &counter);
+    counter.stats()
+}
+
+pub fn sort_stats_by_key<T, S: Sorter, K: PartialOrd, F: FnMut(&T) -> K>(
+    sorter: &S,
+    nums: &mut [T],
+    mut key: F,
+) -> SortStats {
+    sort_stats_by(sorter, nums, |a, b| partial_order(&key(a), &key(b)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::sorter::{Algorithm, Complexity};
+
+    fn random(len: usize, seed: u64) -> Vec<u32> {
+        let mut seed = seed;
+        (0..len)
+            .map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                (seed >> 40) as u32 % 1000
+            })
+            .collect()
+    }
+
+    fn inversions(nums: &[u32]) -> u64 {
+        let mut count = 0;
+        for i in 0..nums.len() {
+            for j in i + 1..nums.len() {
+                count += (nums[i] > nums[j]) as u64;
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn test_every_algorithm_counts_each_comparison_once() {
+        for algorithm in Algorithm::ALL {
+            let mut nums = random(300, 3);
+            let calls = Cell::new(0);
+            let stats = sort_stats_by(&algorithm, &mut nums, |a, b| {
+                calls.set(calls.get() + 1);
+                a.cmp(b)
+            });
+            assert!(nums.windows(2).all(|w| w[0] <= w[1]), "{}", algorithm);
+            assert_eq!(stats.comparisons, calls.get(), "{}", algorithm);
+            assert!(stats.swaps + stats.writes > 0, "{}", algorithm);
+        }
+    }
+
+    #[test]
+    fn test_adjacent_swap_sorts_swap_once_per_inversion() {
+        let nums = random(200, 9);
+        let expected = inversions(&nums);
+        for algorithm in [Algorithm::Bubble, Algorithm::Cocktail, Algorithm::Insertion] {
+            let stats = sort_stats(&algorithm, &mut nums.clone());
+            assert_eq!(stats.swaps, expected, "{}", algorithm);
+            assert_eq!(stats.writes, 0);
+            assert_eq!(stats.allocations, 0);
+            assert_eq!(stats.max_depth, 0);
+        }
+    }
+
+    #[test]
+    fn test_sorted_input() {
+        let mut nums: Vec<u32> = (0..1000).collect();
+        for algorithm in [Algorithm::Bubble, Algorithm::Insertion, Algorithm::Tim] {
+            let stats = sort_stats(&algorithm, &mut nums);
+            assert_eq!(
+                stats,
+                SortStats {
+                    comparisons: 999,
+                    ..SortStats::default()
+                },
+                "{}",
+                algorithm
+            );
+        }
+
+        let stats = sort_stats(&Algorithm::Selection, &mut nums);
+        assert_eq!(stats.comparisons, 1000 * 999 / 2);
+    }
+
+    #[test]
+    fn test_merge_sort_stats() {
+        let mut nums = random(1024, 5);
+        let stats = sort_stats(&Algorithm::Merge, &mut nums);
+        // one scratch buffer, every level moves each element out and back
+        assert_eq!(stats.allocations, 1);
+        assert_eq!(stats.allocated_elements, 1024);
+        assert_eq!(stats.writes, 2 * 1024 * 10);
+        assert_eq!(stats.swaps, 0);
+        assert_eq!(stats.max_depth, 10);
+    }
+
+    #[test]
+    fn test_recursion_depth_is_logarithmic() {
+        for algorithm in [Algorithm::Quick, Algorithm::Pdq] {
+            for nums in [
+                random(10_000, 1),
+                (0..10_000).collect(),
+                (0..10_000).rev().collect(),
+            ] {
+                let stats = sort_stats(&algorithm, &mut nums.clone());
+                assert!(
+                    stats.max_depth >= 1 && stats.max_depth <= 14,
+                    "{}",
+                    algorithm
+                );
+                assert_eq!(stats.allocations, 0);
+            }
+        }
+        let stats = sort_stats(&Algorithm::Heap, &mut random(10_000, 1));
+        assert_eq!(stats.max_depth, 0);
+    }
+
+    #[test]
+    fn test_sort_stats_by_key() {
+        let mut words = vec!["ccc", "a", "bb", "dddd"];
+        let stats = sort_stats_by_key(&Algorithm::Insertion, &mut words, |w| w.len());
+        assert_eq!(words, ["a", "bb", "ccc", "dddd"]);
+        assert_eq!(stats.swaps, 2);
+    }
+
+    #[test]
+    fn test_outside_sorter_reports_comparisons() {
+        struct StdSort;
+
+        impl Sorter for StdSort {
+            fn name(&self) -> &'static str {
+                "std_sort"
+            }
+
+            fn is_stable(&self) -> bool {
+                true
+            }
+
+            fn is_in_place(&self) -> bool {
+                false
+            }
+
+            fn worst_case(&self) -> Complexity {
+                Complexity::NLogN
+            }
+
+            fn sort_by<T, F: FnMut(&T, &T) -> Ordering>(&self, nums: &mut [T], compare: F) {
+                nums.sort_by(compare);
+            }
+        }
+
+        let stats = sort_stats(&StdSort, &mut [3, 1, 2]);
+        assert!(stats.comparisons > 0);
+        assert_eq!(stats.swaps + stats.writes + stats.allocations, 0);
+    }
+}
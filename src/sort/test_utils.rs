@@ -0,0 +1,250 @@
+use super::stats::Observer;
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::panic::{self, AssertUnwindSafe};
+
+// Heap owning element that counts its drops
+// Used to check that a sort neither leaks nor double drops, even when the comparator panics
+pub(crate) struct Counted<'a> {
+    pub(crate) key: u32,
+    payload: String,
+    drops: &'a Cell<usize>,
+}
+
+impl<'a> Counted<'a> {
+    pub(crate) fn new(key: u32, drops: &'a Cell<usize>) -> Self {
+        Self {
+            key,
+            payload: key.to_string(),
+            drops,
+        }
+    }
+}
+
+impl Drop for Counted<'_> {
+    fn drop(&mut self) {
+        self.drops.set(self.drops.get() + 1);
+    }
+}
+
+type Compare<'c, 'a> = &'c mut dyn FnMut(&Counted<'a>, &Counted<'a>) -> Ordering;
+
+//Run the sort with a comparator that panics after a growing number of calls
+//Every element must still be in the slice exactly once and be dropped exactly once
+pub(crate) fn check_panic_safety<S>(sort: S)
+where
+    S: for<'c, 'a> Fn(&mut [Counted<'a>], Compare<'c, 'a>),
+{
+    let panic_points = [0, 1, 7, 50, 200, 1_000, 3_000, usize::MAX];
+    check_unwinding(panic_points, |nums, panic_after| {
+        let mut calls = 0;
+        sort(nums, &mut |a, b| {
+            calls += 1;
+            if calls > panic_after {
+                panic!("comparator gave up");
+            }
+            a.key.cmp(&b.key)
+        })
+    });
+}
+
+// Observer that panics on the first hook call past its limit
+pub(crate) struct PanicAfter {
+    calls: Cell<usize>,
+    limit: usize,
+}
+
+impl PanicAfter {
+    fn tick(&self) {
+        self.calls.set(self.calls.get() + 1);
+        if self.calls.get() > self.limit {
+            panic!("observer gave up");
+        }
+    }
+}
+
+impl<T> Observer<T> for PanicAfter {
+    fn compare(&self, _a: &T, _b: &T) {
+        self.tick();
+    }
+
+    fn swap(&self, _a: &T, _b: &T) {
+        self.tick();
+    }
+
+    fn write(&self, _slot: &T) {
+        self.tick();
+    }
+
+    fn alloc(&self, _len: usize) {
+        self.tick();
+    }
+
+    fn enter(&self) {
+        self.tick();
+    }
+
+    fn leave(&self) {
+        self.tick();
+    }
+}
+
+//Same checks as check_panic_safety, with an observer that panics instead of the comparator
+//Panics are tried every few hook calls, so every kind of move gets interrupted somewhere
+pub(crate) fn check_observer_panic_safety<S>(sort: S)
+where
+    S: for<'a> Fn(&mut [Counted<'a>], &PanicAfter),
+{
+    let panic_points = (0..4_000).step_by(7).chain([20_000, usize::MAX]);
+    check_unwinding(panic_points, |nums, panic_after| {
+        let observer = PanicAfter {
+            calls: Cell::new(0),
+            limit: panic_after,
+        };
+        sort(nums, &observer)
+    });
+}
+
+//Run sort on inputs of Counted, it is expected to panic after panic_after calls to something
+fn check_unwinding<P, S>(panic_points: P, sort: S)
+where
+    P: IntoIterator<Item = usize> + Clone,
+    S: for<'a> Fn(&mut [Counted<'a>], usize),
+{
+    // the long input has ascending runs and needs merges, the short one may only use insertion
+    let mut inputs: Vec<Vec<u32>> = [40u32, 500]
+        .into_iter()
+        .map(|len| (0..len).map(|i| i * 17 % len).collect())
+        .collect();
+    // interleaved sorted columns, merging them makes tim_sort gallop
+    let mut columns: Vec<u32> = (0..200).collect();
+    columns.sort_by_key(|&i| (i % 64, i / 64));
+    let mut ranks = vec![0; columns.len()];
+    for (rank, &i) in (0..).zip(&columns) {
+        ranks[i as usize] = rank;
+    }
+    inputs.push(ranks);
+
+    for keys in inputs {
+        let len = keys.len() as u32;
+        for panic_after in panic_points.clone() {
+            let drops = Cell::new(0);
+            {
+                let mut nums: Vec<Counted> =
+                    keys.iter().map(|&key| Counted::new(key, &drops)).collect();
+                let result = panic::catch_unwind(AssertUnwindSafe(|| sort(&mut nums, panic_after)));
+                if panic_after == usize::MAX {
+                    assert!(result.is_ok());
+                    assert!(nums.windows(2).all(|pair| pair[0].key <= pair[1].key));
+                } else if panic_after == 0 {
+                    assert!(result.is_err());
+                }
+
+                assert_eq!(drops.get(), 0);
+                let mut keys: Vec<u32> = nums.iter().map(|item| item.key).collect();
+                keys.sort_unstable();
+                assert_eq!(keys, (0..len).collect::<Vec<_>>());
+                assert!(nums.iter().all(|item| item.payload == item.key.to_string()));
+            }
+            assert_eq!(drops.get(), len as usize);
+        }
+    }
+}
+
+type Record = (&'static str, f64);
+
+//Check the comparator and key function variants of a sort on small fixed inputs
+pub(crate) fn check_by_variants<B, K>(sort_by: B, sort_by_key: K)
+where
+    B: Fn(&mut [i32], &mut dyn FnMut(&i32, &i32) -> Ordering),
+    K: Fn(&mut [Record], &mut dyn FnMut(&Record) -> f64),
+{
+    let mut nums = [5, 2, 1, 3, 6, 4];
+    sort_by(&mut nums, &mut |a, b| b.cmp(a));
+    assert_eq!(nums, [6, 5, 4, 3, 2, 1]);
+
+    let mut records = [("dog", 3.5), ("cat", 1.25), ("fox", 2.0), ("ant", 0.5)];
+    sort_by_key(&mut records, &mut |&(_, weight)| weight);
+    assert_eq!(
+        records,
+        [("ant", 0.5), ("cat", 1.25), ("fox", 2.0), ("dog", 3.5)]
+    );
+
+    let mut empty: [Record; 0] = [];
+    sort_by_key(&mut empty, &mut |&(_, weight)| weight);
+    assert_eq!(empty, []);
+}
+
+// Record tagged with its position in the input, sorts must compare the key only
+pub(crate) type Tagged = (u32, usize);
+
+//Keys of many shapes at the given length, all with repeated keys
+pub(crate) fn key_shapes(len: usize) -> Vec<(String, Vec<u32>)> {
+    let mut seed = 11 + len as u64;
+    let random: Vec<u32> = (0..len)
+        .map(|_| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+            (seed >> 33) as u32
+        })
+        .collect();
+    let shapes: [(&str, Vec<u32>); 6] = [
+        ("few keys", random.iter().map(|x| x % 4).collect()),
+        (
+            "some duplicates",
+            random.iter().map(|x| x % (len as u32 / 2 + 1)).collect(),
+        ),
+        ("all equal", vec![7; len]),
+        ("ascending pairs", (0..len as u32).map(|i| i / 2).collect()),
+        (
+            "descending pairs",
+            (0..len as u32).rev().map(|i| i / 2).collect(),
+        ),
+        ("sawtooth", (0..len as u32).map(|i| i % 5).collect()),
+    ];
+    shapes
+        .into_iter()
+        .map(|(shape, keys)| (format!("{} of len {}", shape, len), keys))
+        .collect()
+}
+
+//Sort tagged records of many shapes and lengths with sort, which has to order them by key alone
+//Panics if a result is not sorted by key or lost a record,
+//returns the first input where equal keys came out of their original order
+pub(crate) fn check_stability<S: FnMut(&mut [Tagged])>(sort: S) -> Result<(), String> {
+    let inputs = [0, 1, 2, 3, 7, 16, 33, 100, 257, 1_000]
+        .into_iter()
+        .flat_map(key_shapes)
+        .collect();
+    check_stability_on(inputs, sort)
+}
+
+//check_stability on the caller's own keys, each input is named for the messages
+pub(crate) fn check_stability_on<S: FnMut(&mut [Tagged])>(
+    inputs: Vec<(String, Vec<u32>)>,
+    mut sort: S,
+) -> Result<(), String> {
+    let mut unstable = Ok(());
+    for (name, keys) in inputs {
+        let input: Vec<Tagged> = keys.into_iter().zip(0..).collect();
+        let mut records = input.clone();
+        sort(&mut records);
+
+        assert!(
+            records.windows(2).all(|pair| pair[0].0 <= pair[1].0),
+            "{} is not sorted",
+            name
+        );
+        let mut tags: Vec<usize> = records.iter().map(|&(_, tag)| tag).collect();
+        tags.sort_unstable();
+        assert!(
+            tags.iter().copied().eq(0..input.len()),
+            "{} lost records",
+            name
+        );
+
+        if unstable.is_ok() && records.windows(2).any(|pair| pair[0] > pair[1]) {
+            unstable = Err(format!("{} reordered equal keys", name));
+        }
+    }
+    unstable
+}
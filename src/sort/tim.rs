@@ -0,0 +1,693 @@
+use super::insertion::binary_insertion_sort_from;
+use super::partial_order;
+use super::stats::{reverse, Observer, Silent};
+use std::cmp::Ordering;
+use std::ptr;
+use std::slice;
+
+// TimSort (Tim Peters), a stable sort that takes advantage of runs already in the data:
+// - natural runs are found first, strictly descending ones get reversed
+// - runs shorter than min_run are extended with binary_insertion_sort
+// - a stack of pending runs is merged so that run lengths grow like Fibonacci numbers
+// - merges switch to galloping when one side keeps winning
+pub fn tim_sort<T: PartialOrd>(nums: &mut [T]) {
+    tim_sort_by(nums, partial_order);
+}
+
+pub fn tim_sort_by<T, F: FnMut(&T, &T) -> Ordering>(nums: &mut [T], mut compare: F) {
+    tim_sort_observed(nums, &mut compare, &Silent);
+}
+
+pub(crate) fn tim_sort_observed<T, F, O>(nums: &mut [T], compare: &mut F, observer: &O)
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    let len = nums.len();
+    if len < 2 {
+        return;
+    }
+
+    let min_run = min_run(len);
+    let mut state = MergeState {
+        runs: Vec::new(),
+        scratch: Vec::new(),
+        min_gallop: MIN_GALLOP,
+    };
+
+    let mut start = 0;
+    while start < len {
+        let mut run = find_run(&mut nums[start..], compare, observer);
+        if run < min_run {
+            let end = min_run.min(len - start);
+            binary_insertion_sort_from(&mut nums[start..start + end], run, compare, observer);
+            run = end;
+        }
+
+        state.runs.push(Run { start, len: run });
+        state.merge_collapse(nums, compare, observer);
+        start += run;
+    }
+
+    state.force_collapse(nums, compare, observer);
+}
+
+pub fn tim_sort_by_key<T, K: PartialOrd, F: FnMut(&T) -> K>(nums: &mut [T], mut key: F) {
+    tim_sort_by(nums, |a, b| partial_order(&key(a), &key(b)));
+}
+
+// Number of consecutive wins by one side before a merge starts galloping
+const MIN_GALLOP: usize = 7;
+
+fn is_less<T, F: FnMut(&T, &T) -> Ordering>(a: &T, b: &T, compare: &mut F) -> bool {
+    compare(a, b) == Ordering::Less
+}
+
+//Run length between 32 and 64 such that len / min_run is a power of two or just below one
+fn min_run(mut len: usize) -> usize {
+    let mut remainder = 0;
+    while len >= 64 {
+        remainder |= len & 1;
+        len >>= 1;
+    }
+    len + remainder
+}
+
+//Length of the run at the start of nums, a strictly descending run is reversed in place
+//Only strictly descending runs are reversed so equal elements keep their order
+fn find_run<T, F, O>(nums: &mut [T], compare: &mut F, observer: &O) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    let len = nums.len();
+    if len < 2 {
+        return len;
+    }
+
+    let mut end = 2;
+    if is_less(&nums[1], &nums[0], compare) {
+        while end < len && is_less(&nums[end], &nums[end - 1], compare) {
+            end += 1;
+        }
+        reverse(&mut nums[..end], observer);
+    } else {
+        while end < len && !is_less(&nums[end], &nums[end - 1], compare) {
+            end += 1;
+        }
+    }
+    end
+}
+
+//Partition point of pred (true on a prefix of slice), found by galloping from one end
+//Exponential steps narrow down the range, then a binary search finishes it
+fn gallop<T>(slice: &[T], from_end: bool, mut pred: impl FnMut(&T) -> bool) -> usize {
+    let len = slice.len();
+    let (lo, hi) = if from_end {
+        // pred is false for slice[hi..]
+        let mut hi = len;
+        let mut step = 1;
+        while step <= len && !pred(&slice[len - step]) {
+            hi = len - step;
+            step = 2 * step + 1;
+        }
+        (len.saturating_sub(step), hi)
+    } else {
+        // pred is true for slice[..lo]
+        let mut lo = 0;
+        let mut step = 1;
+        while step <= len && pred(&slice[step - 1]) {
+            lo = step;
+            step = 2 * step + 1;
+        }
+        (lo, step.min(len))
+    };
+    lo + slice[lo..hi].partition_point(pred)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Run {
+    start: usize,
+    len: usize,
+}
+
+struct MergeState<T> {
+    runs: Vec<Run>,
+    // Holds the shorter run of a merge, its len stays 0 so it never drops anything
+    scratch: Vec<T>,
+    min_gallop: usize,
+}
+
+impl<T> MergeState<T> {
+    // Merge until the run lengths on the stack satisfy, from the top:
+    // runs[n - 3] > runs[n - 2] + runs[n - 1] and runs[n - 2] > runs[n - 1]
+    // The check also looks at runs[n - 4], which the original algorithm missed
+    fn merge_collapse<F, O>(&mut self, nums: &mut [T], compare: &mut F, observer: &O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer<T>,
+    {
+        while self.runs.len() > 1 {
+            let n = self.runs.len();
+            let len = |i: usize| self.runs[i].len;
+            let at = if (n >= 3 && len(n - 3) <= len(n - 2) + len(n - 1))
+                || (n >= 4 && len(n - 4) <= len(n - 3) + len(n - 2))
+            {
+                if len(n - 3) < len(n - 1) {
+                    n - 3
+                } else {
+                    n - 2
+                }
+            } else if len(n - 2) <= len(n - 1) {
+                n - 2
+            } else {
+                break;
+            };
+            self.merge_at(nums, at, compare, observer);
+        }
+    }
+
+    //Merge whatever is left on the stack once every run was found
+    fn force_collapse<F, O>(&mut self, nums: &mut [T], compare: &mut F, observer: &O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer<T>,
+    {
+        while self.runs.len() > 1 {
+            let n = self.runs.len();
+            let at = if n >= 3 && self.runs[n - 3].len < self.runs[n - 1].len {
+                n - 3
+            } else {
+                n - 2
+            };
+            self.merge_at(nums, at, compare, observer);
+        }
+    }
+
+    //Merge runs[at] with runs[at + 1]
+    fn merge_at<F: FnMut(&T, &T) -> Ordering, O: Observer<T>>(
+        &mut self,
+        nums: &mut [T],
+        at: usize,
+        compare: &mut F,
+        observer: &O,
+    ) {
+        let left = self.runs[at];
+        let right = self.runs.remove(at + 1);
+        self.runs[at].len += right.len;
+
+        let merged = &mut nums[left.start..right.start + right.len];
+        let mid = left.len;
+
+        // Elements of the left run not above the first right element are already in place
+        let skip = gallop(&merged[..mid], false, |x| {
+            !is_less(&merged[mid], x, compare)
+        });
+        if skip == mid {
+            return;
+        }
+        let merged = &mut merged[skip..];
+        let mid = mid - skip;
+
+        // Elements of the right run not below the last left element are already in place
+        let keep = gallop(&merged[mid..], true, |x| {
+            is_less(x, &merged[mid - 1], compare)
+        });
+        // Only an inconsistent comparator, like partial_cmp with NaN, leaves nothing to merge
+        if keep == 0 {
+            return;
+        }
+        let merged = &mut merged[..mid + keep];
+
+        // The shorter run goes to scratch
+        let capacity = self.scratch.capacity();
+        self.scratch.reserve(mid.min(keep));
+        if self.scratch.capacity() > capacity {
+            observer.alloc(self.scratch.capacity());
+        }
+        // SAFETY: scratch is empty with room for the shorter run
+        unsafe {
+            if mid <= keep {
+                merge_lo(
+                    merged,
+                    mid,
+                    &mut self.scratch,
+                    &mut self.min_gallop,
+                    compare,
+                    observer,
+                );
+            } else {
+                merge_hi(
+                    merged,
+                    mid,
+                    &mut self.scratch,
+                    &mut self.min_gallop,
+                    compare,
+                    observer,
+                );
+            }
+        }
+    }
+}
+
+// Elements of scratch[start..end] still waiting to go back to nums[dest..]
+// Copies them back on drop, so nums holds every element exactly once even if compare panics
+// The observer can panic too, so it is only told about a write once the hole is up to date
+struct Hole<T> {
+    start: *mut T,
+    end: *mut T,
+    dest: *mut T,
+}
+
+impl<T> Hole<T> {
+    fn len(&self) -> usize {
+        // SAFETY: start and end point into the same scratch allocation
+        unsafe { self.end.offset_from(self.start) as usize }
+    }
+}
+
+impl<T> Drop for Hole<T> {
+    fn drop(&mut self) {
+        // SAFETY: nums[dest..dest + len] is exactly the gap left by the pending elements
+        unsafe { ptr::copy_nonoverlapping(self.start, self.dest, self.len()) };
+    }
+}
+
+// Merge front to back, the left run nums[..mid] is the shorter one and moves to scratch
+// Either run can be empty, and an inconsistent compare only leaves the result unsorted
+// SAFETY: the caller guarantees scratch is empty with a capacity of at least mid
+unsafe fn merge_lo<T, F: FnMut(&T, &T) -> Ordering, O: Observer<T>>(
+    nums: &mut [T],
+    mid: usize,
+    scratch: &mut Vec<T>,
+    min_gallop: &mut usize,
+    compare: &mut F,
+    observer: &O,
+) {
+    if mid == 0 || mid == nums.len() {
+        return;
+    }
+    let v = nums.as_mut_ptr();
+    let end = v.add(nums.len());
+    let buf = scratch.as_mut_ptr();
+    ptr::copy_nonoverlapping(v, buf, mid);
+    wrote(buf, mid, observer);
+
+    // The gap sits right before the next right element, so writes never overtake reads
+    let mut hole = Hole {
+        start: buf,
+        end: buf.add(mid),
+        dest: v,
+    };
+    let mut right = v.add(mid);
+
+    'merge: loop {
+        let mut count_left = 0;
+        let mut count_right = 0;
+
+        // One element at a time until a side wins min_gallop times in a row
+        loop {
+            if is_less(&*right, &*hole.start, compare) {
+                ptr::copy_nonoverlapping(right, hole.dest, 1);
+                hole.dest = hole.dest.add(1);
+                right = right.add(1);
+                wrote(hole.dest.sub(1), 1, observer);
+                count_right += 1;
+                count_left = 0;
+                if right == end {
+                    break 'merge;
+                }
+            } else {
+                ptr::copy_nonoverlapping(hole.start, hole.dest, 1);
+                hole.dest = hole.dest.add(1);
+                hole.start = hole.start.add(1);
+                wrote(hole.dest.sub(1), 1, observer);
+                count_left += 1;
+                count_right = 0;
+                if hole.start == hole.end {
+                    break 'merge;
+                }
+            }
+            if count_left.max(count_right) >= *min_gallop {
+                break;
+            }
+        }
+
+        // Galloping, move whole stretches found with exponential search
+        loop {
+            let key = &*right;
+            let pending = slice::from_raw_parts(hole.start, hole.len());
+            count_left = gallop(pending, false, |x| !is_less(key, x, compare));
+            ptr::copy_nonoverlapping(hole.start, hole.dest, count_left);
+            hole.dest = hole.dest.add(count_left);
+            hole.start = hole.start.add(count_left);
+            wrote(hole.dest.sub(count_left), count_left, observer);
+            if hole.start == hole.end {
+                break 'merge;
+            }
+
+            ptr::copy_nonoverlapping(right, hole.dest, 1);
+            hole.dest = hole.dest.add(1);
+            right = right.add(1);
+            wrote(hole.dest.sub(1), 1, observer);
+            if right == end {
+                break 'merge;
+            }
+
+            let key = &*hole.start;
+            let rest = slice::from_raw_parts(right, end.offset_from(right) as usize);
+            count_right = gallop(rest, false, |x| is_less(x, key, compare));
+            // The gap can be shorter than the stretch, so the ranges may overlap
+            ptr::copy(right, hole.dest, count_right);
+            hole.dest = hole.dest.add(count_right);
+            right = right.add(count_right);
+            wrote(hole.dest.sub(count_right), count_right, observer);
+            if right == end {
+                break 'merge;
+            }
+
+            ptr::copy_nonoverlapping(hole.start, hole.dest, 1);
+            hole.dest = hole.dest.add(1);
+            hole.start = hole.start.add(1);
+            wrote(hole.dest.sub(1), 1, observer);
+            if hole.start == hole.end {
+                break 'merge;
+            }
+
+            // Galloping pays off, make it easier to get back into it
+            *min_gallop = min_gallop.saturating_sub(1);
+            if count_left < MIN_GALLOP && count_right < MIN_GALLOP {
+                break;
+            }
+        }
+        *min_gallop += 2;
+    }
+
+    *min_gallop = (*min_gallop).max(1);
+    // copy what is left of the left run to the end
+    let (dest, pending) = (hole.dest, hole.len());
+    drop(hole);
+    wrote(dest, pending, observer);
+}
+
+// Merge back to front, the right run nums[mid..] is the shorter one and moves to scratch
+// Either run can be empty, and an inconsistent compare only leaves the result unsorted
+// SAFETY: the caller guarantees scratch is empty with a capacity of at least nums.len() - mid
+unsafe fn merge_hi<T, F: FnMut(&T, &T) -> Ordering, O: Observer<T>>(
+    nums: &mut [T],
+    mid: usize,
+    scratch: &mut Vec<T>,
+    min_gallop: &mut usize,
+    compare: &mut F,
+    observer: &O,
+) {
+    if mid == 0 || mid == nums.len() {
+        return;
+    }
+    let v = nums.as_mut_ptr();
+    let right_len = nums.len() - mid;
+    let buf = scratch.as_mut_ptr();
+    ptr::copy_nonoverlapping(v.add(mid), buf, right_len);
+    wrote(buf, right_len, observer);
+
+    // The left run is v[..hole.dest] and the gap nums[hole.dest..hole.dest + hole.len()]
+    // sits right after it, so the next output slot is the last one of the gap
+    let mut hole = Hole {
+        start: buf,
+        end: buf.add(right_len),
+        dest: v.add(mid),
+    };
+
+    'merge: loop {
+        let mut count_left = 0;
+        let mut count_right = 0;
+
+        // One element at a time until a side wins min_gallop times in a row
+        loop {
+            let out = hole.dest.add(hole.len() - 1);
+            if is_less(&*hole.end.sub(1), &*hole.dest.sub(1), compare) {
+                ptr::copy_nonoverlapping(hole.dest.sub(1), out, 1);
+                hole.dest = hole.dest.sub(1);
+                wrote(out, 1, observer);
+                count_left += 1;
+                count_right = 0;
+                if hole.dest == v {
+                    break 'merge;
+                }
+            } else {
+                ptr::copy_nonoverlapping(hole.end.sub(1), out, 1);
+                hole.end = hole.end.sub(1);
+                wrote(out, 1, observer);
+                count_right += 1;
+                count_left = 0;
+                if hole.start == hole.end {
+                    break 'merge;
+                }
+            }
+            if count_left.max(count_right) >= *min_gallop {
+                break;
+            }
+        }
+
+        // Galloping, move whole stretches found with exponential search
+        loop {
+            let key = &*hole.end.sub(1);
+            let left_len = hole.dest.offset_from(v) as usize;
+            let left = slice::from_raw_parts(v, left_len);
+            count_left = left_len - gallop(left, true, |x| !is_less(key, x, compare));
+            // The gap can be shorter than the stretch, so the ranges may overlap
+            let out_end = hole.dest.add(hole.len());
+            ptr::copy(
+                hole.dest.sub(count_left),
+                out_end.sub(count_left),
+                count_left,
+            );
+            hole.dest = hole.dest.sub(count_left);
+            wrote(out_end.sub(count_left), count_left, observer);
+            if hole.dest == v {
+                break 'merge;
+            }
+
+            let out = hole.dest.add(hole.len() - 1);
+            ptr::copy_nonoverlapping(hole.end.sub(1), out, 1);
+            hole.end = hole.end.sub(1);
+            wrote(out, 1, observer);
+            if hole.start == hole.end {
+                break 'merge;
+            }
+
+            let key = &*hole.dest.sub(1);
+            let pending = slice::from_raw_parts(hole.start, hole.len());
+            count_right = hole.len() - gallop(pending, true, |x| is_less(x, key, compare));
+            let out_end = hole.dest.add(hole.len());
+            ptr::copy_nonoverlapping(
+                hole.end.sub(count_right),
+                out_end.sub(count_right),
+                count_right,
+            );
+            hole.end = hole.end.sub(count_right);
+            wrote(out_end.sub(count_right), count_right, observer);
+            if hole.start == hole.end {
+                break 'merge;
+            }
+
+            let out = hole.dest.add(hole.len() - 1);
+            ptr::copy_nonoverlapping(hole.dest.sub(1), out, 1);
+            hole.dest = hole.dest.sub(1);
+            wrote(out, 1, observer);
+            if hole.dest == v {
+                break 'merge;
+            }
+
+            // Galloping pays off, make it easier to get back into it
+            *min_gallop = min_gallop.saturating_sub(1);
+            if count_left < MIN_GALLOP && count_right < MIN_GALLOP {
+                break;
+            }
+        }
+        *min_gallop += 2;
+    }
+
+    *min_gallop = (*min_gallop).max(1);
+    // copy what is left of the right run to the front
+    let (dest, pending) = (hole.dest, hole.len());
+    drop(hole);
+    wrote(dest, pending, observer);
+}
+
+//Report count elements just moved to dest
+// SAFETY: dest[..count] must hold initialized elements
+unsafe fn wrote<T, O: Observer<T>>(dest: *const T, count: usize, observer: &O) {
+    for i in 0..count {
+        observer.write(&*dest.add(i));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::test_utils::{
+        check_panic_safety, check_stability, check_stability_on, Tagged,
+    };
+
+    fn pseudo_random(len: usize, modulo: u32) -> Vec<u32> {
+        let mut seed: u64 = 3;
+        (0..len)
+            .map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                (seed >> 33) as u32 % modulo
+            })
+            .collect()
+    }
+
+    fn tim_sort_tagged(records: &mut [Tagged]) {
+        tim_sort_by_key(records, |&(key, _)| key);
+    }
+
+    #[test]
+    fn test_tim_sort_small() {
+        let mut empty: Vec<i32> = Vec::new();
+        tim_sort(&mut empty);
+        assert_eq!(empty, vec![]);
+
+        let mut nums = [5, 2, 1, 3, 6, 4];
+        tim_sort(&mut nums);
+        assert_eq!(nums, [1, 2, 3, 4, 5, 6]);
+
+        let mut words = ["apple", "dog", "cat", "banana", "elephant", "fox"];
+        tim_sort_by(&mut words, |a, b| b.cmp(a));
+        assert_eq!(words, ["fox", "elephant", "dog", "cat", "banana", "apple"]);
+    }
+
+    #[test]
+    fn test_tim_sort_is_stable() {
+        check_stability(tim_sort_tagged).unwrap();
+
+        let inputs = [(50, 3), (1_000, 10), (10_000, 50), (10_000, 10_000)]
+            .into_iter()
+            .map(|(len, modulo)| {
+                (
+                    format!("{} keys below {}", len, modulo),
+                    pseudo_random(len, modulo),
+                )
+            })
+            .collect();
+        check_stability_on(inputs, tim_sort_tagged).unwrap();
+    }
+
+    #[test]
+    fn test_tim_sort_log_records() {
+        // Mostly sorted timestamps with a few late arrivals and duplicate seconds
+        let mut keys: Vec<u32> = (0..20_000).map(|i| i / 3).collect();
+        for i in (0..keys.len()).step_by(97) {
+            keys[i] = keys[i].saturating_sub(40);
+        }
+
+        // Several sorted chunks glued together, merges gallop over whole chunks
+        let chunks: Vec<u32> = (0..8)
+            .flat_map(|chunk| (0..2_000).map(move |i| i * 8 + chunk))
+            .collect();
+        let inputs = vec![
+            ("log records".to_string(), keys),
+            ("chunks".to_string(), chunks),
+        ];
+        check_stability_on(inputs, tim_sort_tagged).unwrap();
+    }
+
+    #[test]
+    fn test_tim_sort_descending_runs() {
+        // Strictly descending runs get reversed, runs with ties must not be
+        let inputs = vec![
+            (
+                "strictly descending".to_string(),
+                (0..5_000).rev().collect(),
+            ),
+            (
+                "descending with ties".to_string(),
+                (0..5_000).rev().map(|i| i / 4).collect(),
+            ),
+            (
+                "zigzag".to_string(),
+                (0..6_000)
+                    .map(|i| if i % 2 == 0 { i / 2 } else { 6_000 - i })
+                    .collect(),
+            ),
+        ];
+        check_stability_on(inputs, tim_sort_tagged).unwrap();
+    }
+
+    #[test]
+    fn test_tim_sort_sorted_is_linear() {
+        let nums: Vec<u64> = (0..10_000).collect();
+        let mut sorted = nums.clone();
+        let mut comparisons = 0;
+        tim_sort_by(&mut sorted, |a, b| {
+            comparisons += 1;
+            a.cmp(b)
+        });
+        assert_eq!(sorted, nums);
+        assert!(comparisons < nums.len());
+    }
+
+    #[test]
+    fn test_gallop() {
+        let nums = [1, 2, 2, 2, 3, 5, 8, 8, 9];
+        for key in 0..10 {
+            let below = nums.iter().filter(|&&x| x < key).count();
+            let not_above = nums.iter().filter(|&&x| x <= key).count();
+            for from_end in [false, true] {
+                assert_eq!(gallop(&nums, from_end, |&x| x < key), below);
+                assert_eq!(gallop(&nums, from_end, |&x| x <= key), not_above);
+            }
+        }
+        assert_eq!(gallop(&[] as &[i32], false, |_| true), 0);
+        assert_eq!(gallop(&[] as &[i32], true, |_| true), 0);
+    }
+
+    #[test]
+    fn test_min_run() {
+        assert_eq!(min_run(10), 10);
+        assert_eq!(min_run(64), 32);
+        assert_eq!(min_run(65), 33);
+        assert!((0..100_000).all(|len| len < 64 || (32..=64).contains(&min_run(len))));
+    }
+
+    #[test]
+    fn test_tim_sort_nan_keeps_every_element() {
+        // NaN compares equal to everything, so the right run gallops to nothing after it
+        let mut nums = vec![1.0, 0.0];
+        nums.extend((2..=30).map(f64::from));
+        nums.push(f64::NAN);
+        nums.extend((0..32).map(|i| f64::from(i) + 0.5));
+        assert_eq!(nums.len(), 64);
+
+        let mut sorted = nums.clone();
+        tim_sort_by(&mut sorted, crate::sort::partial_order);
+        assert_eq!(sorted.iter().filter(|x| x.is_nan()).count(), 1);
+        let mut expected: Vec<f64> = nums.into_iter().filter(|x| !x.is_nan()).collect();
+        let mut got: Vec<f64> = sorted.into_iter().filter(|x| !x.is_nan()).collect();
+        expected.sort_by(f64::total_cmp);
+        got.sort_by(f64::total_cmp);
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn test_tim_sort_random_comparator_keeps_every_element() {
+        for (len, seed) in [(64, 1u64), (200, 2), (1_000, 3), (5_000, 4)] {
+            let nums: Vec<u64> = (0..len).collect();
+            let mut shuffled = nums.clone();
+            let mut state = seed;
+            tim_sort_by(&mut shuffled, |_, _| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+                [Ordering::Less, Ordering::Equal, Ordering::Greater][(state >> 33) as usize % 3]
+            });
+            shuffled.sort();
+            assert_eq!(shuffled, nums);
+        }
+    }
+
+    #[test]
+    fn test_tim_sort_panic_safety() {
+        check_panic_safety(|nums, compare| tim_sort_by(nums, compare));
+    }
+}
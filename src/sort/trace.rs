@@ -0,0 +1,160 @@
+use super::partial_order;
+use super::sorter::Sorter;
+use super::stats::Observer;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::mem;
+
+pub mod render;
+
+// Step by step record of a sort, built on the same hooks as stats::sort_stats
+// Replaying the events on the input gives back the sorted slice, the renderers draw each step
+
+// Where an element involved in an event sits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Position {
+    //Index into the slice being sorted
+    Slice(usize),
+    //Outside of it, in a scratch buffer or a temporary
+    Aux,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SortEvent<T> {
+    Compare(Position, Position),
+    Swap(usize, usize),
+    //value was moved into the position
+    Write(Position, T),
+}
+
+// Records events for the slice starting at address base
+struct Tracer<T> {
+    base: usize,
+    len: usize,
+    events: RefCell<Vec<SortEvent<T>>>,
+}
+
+impl<T> Tracer<T> {
+    fn new(nums: &[T]) -> Self {
+        Self {
+            base: nums.as_ptr() as usize,
+            len: nums.len(),
+            events: RefCell::new(Vec::new()),
+        }
+    }
+
+    //Index of slot from its address, zero sized elements all count as Aux
+    fn position(&self, slot: &T) -> Position {
+        let size = mem::size_of::<T>();
+        let offset = (slot as *const T as usize).wrapping_sub(self.base);
+        if size > 0 && offset < self.len * size {
+            Position::Slice(offset / size)
+        } else {
+            Position::Aux
+        }
+    }
+
+    fn push(&self, event: SortEvent<T>) {
+        self.events.borrow_mut().push(event);
+    }
+}
+
+impl<T: Clone> Observer<T> for Tracer<T> {
+    fn compare(&self, a: &T, b: &T) {
+        self.push(SortEvent::Compare(self.position(a), self.position(b)));
+    }
+
+    fn swap(&self, a: &T, b: &T) {
+        if let (Position::Slice(i), Position::Slice(j)) = (self.position(a), self.position(b)) {
+            self.push(SortEvent::Swap(i, j));
+        }
+    }
+
+    fn write(&self, slot: &T) {
+        self.push(SortEvent::Write(self.position(slot), slot.clone()));
+    }
+}
+
+//Sort nums with sorter and return every step it took
+pub fn trace<T: PartialOrd + Clone, S: Sorter>(sorter: &S, nums: &mut [T]) -> Vec<SortEvent<T>> {
+    trace_by(sorter, nums, partial_order)
+}
+
+pub fn trace_by<T, S, F>(sorter: &S, nums: &mut [T], compare: F) -> Vec<SortEvent<T>>
+where
+    T: Clone,
+    S: Sorter,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let tracer = Tracer::new(nums);
+    sorter.sort_observed(nums, compare, &tracer);
+    tracer.events.into_inner()
+}
+
+//Apply one event to nums, compares and writes to Aux change nothing
+pub fn apply<T: Clone>(nums: &mut [T], event: &SortEvent<T>) {
+    match event {
+        SortEvent::Compare(..) | SortEvent::Write(Position::Aux, _) => {}
+        SortEvent::Swap(i, j) => nums.swap(*i, *j),
+        SortEvent::Write(Position::Slice(i), value) => nums[*i] = value.clone(),
+    }
+}
+
+//State of the slice after every event of the trace
+pub fn replay<T: Clone>(initial: &[T], trace: &[SortEvent<T>]) -> Vec<T> {
+    let mut nums = initial.to_vec();
+    for event in trace {
+        apply(&mut nums, event);
+    }
+    nums
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::sorter::Algorithm;
+
+    #[test]
+    fn test_replay_gives_the_sorted_slice() {
+        let initial: Vec<u32> = (0..200).map(|x| (x * 7919 + 13) % 101).collect();
+        for algorithm in Algorithm::ALL {
+            let mut nums = initial.clone();
+            let events = trace(&algorithm, &mut nums);
+            assert!(nums.windows(2).all(|w| w[0] <= w[1]), "{}", algorithm);
+            assert_eq!(replay(&initial, &events), nums, "{}", algorithm);
+        }
+    }
+
+    #[test]
+    fn test_insertion_sort_trace() {
+        let mut nums = [2, 3, 1];
+        let events = trace(&Algorithm::Insertion, &mut nums);
+        let slice = Position::Slice;
+        assert_eq!(
+            events,
+            vec![
+                SortEvent::Compare(slice(1), slice(0)),
+                SortEvent::Compare(slice(2), slice(1)),
+                SortEvent::Swap(2, 1),
+                SortEvent::Compare(slice(1), slice(0)),
+                SortEvent::Swap(1, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_sort_trace_uses_aux() {
+        let mut nums = ['b', 'a'];
+        let events = trace_by(&Algorithm::Merge, &mut nums, |a, b| a.cmp(b));
+        assert_eq!(
+            events,
+            vec![
+                SortEvent::Compare(Position::Slice(0), Position::Slice(1)),
+                SortEvent::Write(Position::Aux, 'a'),
+                SortEvent::Write(Position::Aux, 'b'),
+                SortEvent::Write(Position::Slice(0), 'a'),
+                SortEvent::Write(Position::Slice(1), 'b'),
+            ]
+        );
+    }
+}
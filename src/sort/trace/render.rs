@@ -0,0 +1,296 @@
+use super::{apply, Position, SortEvent};
+use std::fmt::{self, Write};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Renderers for a trace: one frame for the input, then one per event
+// Values are drawn as bars, so the elements have to be unsigned integers
+
+// Widest ASCII bar, longer ones are scaled down
+const ASCII_WIDTH: u64 = 60;
+// SVG bar size in pixels and time each frame stays on screen
+const BAR_WIDTH: usize = 16;
+const BAR_GAP: usize = 4;
+const SVG_HEIGHT: u64 = 200;
+const FRAME_SECONDS: f64 = 0.25;
+
+const COLOR_IDLE: &str = "steelblue";
+const COLOR_COMPARED: &str = "orange";
+const COLOR_MOVED: &str = "crimson";
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Position::Slice(index) => write!(f, "{}", index),
+            Position::Aux => write!(f, "aux"),
+        }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for SortEvent<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SortEvent::Compare(a, b) => write!(f, "compare {} {}", a, b),
+            SortEvent::Swap(i, j) => write!(f, "swap {} {}", i, j),
+            SortEvent::Write(position, value) => write!(f, "write {} to {}", value, position),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mark {
+    Idle,
+    Compared,
+    Moved,
+}
+
+//Which indices of the slice the event touched
+fn marks<T>(len: usize, event: &SortEvent<T>) -> Vec<Mark> {
+    let mut marks = vec![Mark::Idle; len];
+    match event {
+        SortEvent::Compare(a, b) => {
+            for position in [a, b] {
+                if let Position::Slice(i) = position {
+                    marks[*i] = Mark::Compared;
+                }
+            }
+        }
+        SortEvent::Swap(i, j) => {
+            marks[*i] = Mark::Moved;
+            marks[*j] = Mark::Moved;
+        }
+        SortEvent::Write(Position::Slice(i), _) => marks[*i] = Mark::Moved,
+        SortEvent::Write(Position::Aux, _) => {}
+    }
+    marks
+}
+
+//The slice and marks after each event, starting with the input
+fn frames<T: Copy>(initial: &[T], trace: &[SortEvent<T>]) -> Vec<(Vec<T>, Vec<Mark>)> {
+    let mut nums = initial.to_vec();
+    let mut frames = vec![(nums.clone(), vec![Mark::Idle; nums.len()])];
+    for event in trace {
+        apply(&mut nums, event);
+        frames.push((nums.clone(), marks(nums.len(), event)));
+    }
+    frames
+}
+
+//Length of the bar for value when the biggest value gets full
+fn scale(value: u64, max: u64, full: u64) -> u64 {
+    if max == 0 {
+        0
+    } else {
+        // in u128, value * full does not fit in a u64 for values near u64::MAX
+        (u128::from(value) * u128::from(full)).div_ceil(u128::from(max)) as u64
+    }
+}
+
+//Text frames, one bar per element, > marks compared elements and * moved ones
+pub fn render_ascii<T: Copy + Into<u64> + fmt::Display>(
+    initial: &[T],
+    trace: &[SortEvent<T>],
+) -> String {
+    let max = initial.iter().map(|&x| x.into()).max().unwrap_or(0);
+    let full = max.min(ASCII_WIDTH);
+    let width = initial
+        .iter()
+        .map(|x| x.to_string().len())
+        .max()
+        .unwrap_or(0);
+
+    let mut out = String::new();
+    for (step, (nums, marks)) in frames(initial, trace).iter().enumerate() {
+        if step == 0 {
+            out.push_str("input\n");
+        } else {
+            let _ = writeln!(out, "step {}: {}", step, trace[step - 1]);
+        }
+        for (value, mark) in nums.iter().zip(marks) {
+            let marker = match mark {
+                Mark::Idle => ' ',
+                Mark::Compared => '>',
+                Mark::Moved => '*',
+            };
+            let bar = "#".repeat(scale((*value).into(), max, full) as usize);
+            let _ = writeln!(out, "{} {:>width$} |{}", marker, value, bar, width = width);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+//Animated SVG, every bar steps through its heights and colors frame by frame and loops
+pub fn render_svg<T: Copy + Into<u64>>(initial: &[T], trace: &[SortEvent<T>]) -> String {
+    let max = initial.iter().map(|&x| x.into()).max().unwrap_or(0);
+    let frames = frames(initial, trace);
+    let width = initial.len() * (BAR_WIDTH + BAR_GAP) + BAR_GAP;
+    let height = SVG_HEIGHT + 2 * BAR_GAP as u64;
+    let duration = frames.len() as f64 * FRAME_SECONDS;
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = width,
+        h = height
+    );
+    let _ = writeln!(
+        out,
+        r#"<rect width="{}" height="{}" fill="white"/>"#,
+        width, height
+    );
+    for i in 0..initial.len() {
+        let heights: Vec<u64> = frames
+            .iter()
+            .map(|(nums, _)| scale(nums[i].into(), max, SVG_HEIGHT))
+            .collect();
+        let ys: Vec<String> = heights
+            .iter()
+            .map(|h| (height - BAR_GAP as u64 - h).to_string())
+            .collect();
+        let heights: Vec<String> = heights.iter().map(u64::to_string).collect();
+        let colors: Vec<&str> = frames
+            .iter()
+            .map(|(_, marks)| match marks[i] {
+                Mark::Idle => COLOR_IDLE,
+                Mark::Compared => COLOR_COMPARED,
+                Mark::Moved => COLOR_MOVED,
+            })
+            .collect();
+
+        let _ = writeln!(
+            out,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}">"#,
+            BAR_GAP + i * (BAR_WIDTH + BAR_GAP),
+            ys[0],
+            BAR_WIDTH,
+            heights[0],
+            COLOR_IDLE
+        );
+        for (attribute, values) in [
+            ("y", ys.join(";")),
+            ("height", heights.join(";")),
+            ("fill", colors.join(";")),
+        ] {
+            let _ = writeln!(
+                out,
+                r#"<animate attributeName="{}" values="{}" dur="{:.2}s" calcMode="discrete" repeatCount="indefinite"/>"#,
+                attribute, values, duration
+            );
+        }
+        out.push_str("</rect>\n");
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+//Write the animated SVG of a trace to path
+pub fn save_svg<T: Copy + Into<u64>, P: AsRef<Path>>(
+    path: P,
+    initial: &[T],
+    trace: &[SortEvent<T>],
+) -> io::Result<()> {
+    fs::write(path, render_svg(initial, trace))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::sorter::Algorithm;
+    use crate::sort::trace::trace;
+    use std::env;
+    use std::path::PathBuf;
+
+    // Compare against testdata/trace/<name>, UPDATE_GOLDEN=1 rewrites the files instead
+    fn check_golden(name: &str, actual: &str) {
+        let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "testdata", "trace", name]
+            .iter()
+            .collect();
+        if env::var_os("UPDATE_GOLDEN").is_some() {
+            fs::write(&path, actual).unwrap();
+            return;
+        }
+        let expected = fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("{}: {}, run with UPDATE_GOLDEN=1", path.display(), e));
+        assert_eq!(actual, expected, "{} is out of date", name);
+    }
+
+    const INPUT: [u32; 5] = [3, 1, 4, 1, 5];
+    // quick_sort hands slices below its insertion threshold of 16 to insertion_sort,
+    // so it needs a longer input to show a partition
+    const QUICK_INPUT: [u32; 17] = [9, 3, 14, 1, 7, 12, 5, 16, 2, 10, 6, 15, 4, 11, 8, 13, 1];
+
+    fn ascii(algorithm: Algorithm, input: &[u32]) -> String {
+        let mut nums = input.to_vec();
+        let events = trace(&algorithm, &mut nums);
+        render_ascii(input, &events)
+    }
+
+    #[test]
+    fn test_golden_ascii() {
+        for (algorithm, input, name) in [
+            (Algorithm::Cocktail, &INPUT[..], "cocktail_sort.txt"),
+            (Algorithm::Heap, &INPUT[..], "heap_sort.txt"),
+            (Algorithm::Quick, &QUICK_INPUT[..], "quick_sort.txt"),
+            (Algorithm::Merge, &INPUT[..], "merge_sort.txt"),
+        ] {
+            check_golden(name, &ascii(algorithm, input));
+        }
+    }
+
+    #[test]
+    fn test_golden_svg() {
+        let mut nums = INPUT;
+        let events = trace(&Algorithm::Cocktail, &mut nums);
+        check_golden("cocktail_sort.svg", &render_svg(&INPUT, &events));
+    }
+
+    #[test]
+    fn test_render_frames() {
+        let events = [
+            SortEvent::Compare(Position::Slice(0), Position::Slice(1)),
+            SortEvent::Swap(0, 1),
+        ];
+        let expected = "\
+input
+  2 |##
+  1 |#
+
+step 1: compare 0 1
+> 2 |##
+> 1 |#
+
+step 2: swap 0 1
+* 1 |#
+* 2 |##
+
+";
+        assert_eq!(render_ascii(&[2u8, 1], &events), expected);
+
+        let svg = render_svg(&[2u8, 1], &events);
+        assert_eq!(svg.matches("<rect x=").count(), 2);
+        assert!(svg.contains(r#"values="steelblue;orange;crimson""#));
+        assert!(svg.contains(r#"dur="0.75s""#));
+    }
+
+    #[test]
+    fn test_render_scales_and_saves() {
+        let text = render_ascii(&[1000u32, 10], &[]);
+        assert!(text.contains(&format!("1000 |{}\n", "#".repeat(60))));
+        assert!(text.contains("  10 |#\n"));
+        assert_eq!(render_ascii::<u32>(&[], &[]), "input\n\n");
+
+        // bar lengths are computed without overflowing near u64::MAX
+        assert_eq!(scale(u64::MAX, u64::MAX, 200), 200);
+        assert_eq!(scale(u64::MAX / 2, u64::MAX, 200), 100);
+        let svg = render_svg(&[u64::MAX, u64::MAX / 4], &[]);
+        assert_eq!(svg.matches("<rect x=").count(), 2);
+
+        let path = env::temp_dir().join(format!("algo-ds-rs-trace-{}.svg", std::process::id()));
+        save_svg(&path, &[1u8], &[]).unwrap();
+        assert!(fs::read_to_string(&path).unwrap().starts_with("<svg"));
+        fs::remove_file(&path).unwrap();
+    }
+}